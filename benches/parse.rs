@@ -0,0 +1,50 @@
+// the crate is only a binary, so the bench pulls in the modules the
+// assignments scraper needs, laid out the same way as in main.rs
+#[allow(dead_code)]
+#[path = "../src/assignment.rs"]
+mod assignment;
+#[allow(dead_code)]
+#[path = "../src/clock.rs"]
+mod clock;
+#[allow(dead_code)]
+#[path = "../src/config.rs"]
+mod config;
+#[allow(dead_code)]
+#[path = "../src/instance.rs"]
+mod instance;
+#[allow(dead_code)]
+#[path = "../src/paths.rs"]
+mod paths;
+// its tests are compiled out of a bench, leaving their imports unused
+#[allow(dead_code, unused_imports)]
+#[path = "../src/scrape.rs"]
+mod scrape;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const ASSIGNMENTS_PAGE: &str = include_str!("../fixtures/assignments.html");
+
+// the fixture's rows over and over, for a course with a long semester
+fn large_page(copies: usize) -> String {
+    let start = ASSIGNMENTS_PAGE.find("<tbody>").expect("fixture has a table body") + "<tbody>".len();
+    let end = ASSIGNMENTS_PAGE.find("</tbody>").expect("fixture has a table body");
+
+    let mut page = ASSIGNMENTS_PAGE[..start].to_owned();
+    page.push_str(&ASSIGNMENTS_PAGE[start..end].repeat(copies));
+    page.push_str(&ASSIGNMENTS_PAGE[end..]);
+    page
+}
+
+fn parse_assignments(c: &mut Criterion) {
+    c.bench_function("parse_assignments fixture", |b| {
+        b.iter(|| scrape::parse_assignments(black_box(ASSIGNMENTS_PAGE), true).unwrap())
+    });
+
+    let large = large_page(750);
+    c.bench_function("parse_assignments 3000 rows", |b| {
+        b.iter(|| scrape::parse_assignments(black_box(&large), true).unwrap())
+    });
+}
+
+criterion_group!(benches, parse_assignments);
+criterion_main!(benches);
@@ -1,14 +1,37 @@
-use chrono::{DateTime, Duration, FixedOffset, Local};
+use chrono::{DateTime, Duration, FixedOffset};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::instance::Instance;
 
 // represents an assignment with additional metadata from scraping: the
 // name, relative link, if the assignment was graded, and its due date
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Assignment {
     pub(crate) name: String,
     pub(crate) id: i64,
     pub(crate) grade: Option<f64>,
     pub(crate) weight: f64,
-    pub(crate) due_date: DateTime<FixedOffset>,
+    // surveys, extra credit and the like may have no deadline at all
+    pub(crate) due_date: Option<DateTime<FixedOffset>>,
+    // some courses grade out of points rather than a percentage; `grade` is
+    // still the percentage, worked out from these
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) points: Option<Points>,
+}
+
+// a grade shown as "earned / possible"
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Points {
+    pub(crate) earned: f64,
+    pub(crate) possible: f64,
+}
+
+impl Points {
+    pub fn percent(&self) -> f64 {
+        self.earned / self.possible * 100.0
+    }
 }
 
 impl Assignment {
@@ -17,7 +40,7 @@ impl Assignment {
         id: i64,
         grade: Option<f64>,
         weight: f64,
-        due_date: DateTime<FixedOffset>,
+        due_date: Option<DateTime<FixedOffset>>,
     ) -> Assignment {
         Assignment {
             name,
@@ -25,24 +48,28 @@ impl Assignment {
             grade,
             weight,
             due_date,
+            points: None,
         }
     }
 
+    // an assignment without a deadline is never late
     pub fn late(&self) -> bool {
-        let now = Local::now();
-        now >= self.due_date
+        self.due_date.is_some_and(|due_date| clock::now() >= due_date)
+    }
+
+    pub fn how_late(&self) -> Option<Duration> {
+        self.due_date.map(|due_date| clock::now() - due_date)
     }
 
-    pub fn how_late(&self) -> Duration {
-        let now = Local::now();
-        now - self.due_date.with_timezone(&Local)
+    pub fn time_left(&self) -> Option<Duration> {
+        self.how_late().map(|how_late| -how_late)
     }
 
     pub fn graded(&self) -> bool {
         self.grade.is_some()
     }
 
-    pub fn submission_link(&self, course_id: i64) -> String {
-        format!("https://handins.ccs.neu.edu/courses/{}/assignments/{}/submissions/new", course_id, self.id)
+    pub fn submission_link(&self, instance: &dyn Instance, course_id: i64) -> String {
+        instance.url(&format!("courses/{}/assignments/{}/submissions/new", course_id, self.id))
     }
 }
\ No newline at end of file
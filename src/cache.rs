@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assignment::Assignment;
+use crate::client::{Handins, Validators};
+use crate::paths;
+
+// the last assignments list fetched for a course, along with what handins
+// identified that version of the page by, so the next fetch can ask whether
+// anything changed instead of downloading and parsing it all again
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CachedAssignments {
+    // which parser produced `assignments`; caches from before this was
+    // recorded come out as 0
+    #[serde(default)]
+    pub(crate) version: u32,
+    pub(crate) validators: Validators,
+    pub(crate) assignments: Vec<Assignment>,
+}
+
+// bump this whenever parse_assignments changes what it reads out of a page,
+// so that lists parsed the old way get fetched and parsed again
+pub(crate) const CACHE_VERSION: u32 = 1;
+
+// grades differ between accounts, so each profile gets its own copy
+fn assignments_path(client: &Handins, course: i64) -> Option<PathBuf> {
+    let host = instance_key(client.instance().base_url());
+    let name = match client.profile() {
+        Some(profile) => format!("{}-{}-{}.json", host, profile, course),
+        None => format!("{}-{}.json", host, course),
+    };
+    paths::cache_dir().map(|dir| dir.join("assignments").join(name))
+}
+
+// the instance's whole base url, port and path included, so that two
+// instances on one host don't share a cache: `http://localhost:8080/handins/`
+// becomes `localhost_8080_handins`
+fn instance_key(base_url: &str) -> String {
+    let url = base_url.split_once("://").map_or(base_url, |(_, rest)| rest);
+    let key: String = url
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+        .collect();
+    key.trim_matches('_').to_owned()
+}
+
+// a missing, unreadable or outdated cache is just a cache miss
+pub(crate) fn load_assignments(client: &Handins, course: i64) -> Option<CachedAssignments> {
+    let contents = std::fs::read_to_string(assignments_path(client, course)?).ok()?;
+    serde_json::from_str(&contents)
+        .ok()
+        .filter(|cached: &CachedAssignments| cached.version == CACHE_VERSION)
+}
+
+// the cache is only an optimization, so failing to write it only complains
+pub(crate) fn store_assignments(client: &Handins, course: i64, cached: &CachedAssignments) {
+    let path = match assignments_path(client, course) {
+        Some(path) => path,
+        None => return,
+    };
+
+    let written = serde_json::to_string(cached)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            std::fs::create_dir_all(path.parent().unwrap_or(&path))
+                .and_then(|_| std::fs::write(&path, json))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = written {
+        eprintln!("warning: couldn't cache assignments at {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instances_on_one_host_get_their_own_cache() {
+        assert_eq!(instance_key("https://handins.ccs.neu.edu/"), "handins.ccs.neu.edu");
+        assert_eq!(instance_key("http://127.0.0.1:8080/"), "127.0.0.1_8080");
+        assert_eq!(instance_key("http://127.0.0.1:8081/"), "127.0.0.1_8081");
+        assert_eq!(instance_key("https://example.edu/handins/"), "example.edu_handins");
+    }
+}
@@ -0,0 +1,368 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex as SyncMutex;
+use std::time::Duration;
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, REFERER};
+use reqwest::{Client, IntoUrl, RequestBuilder, Response, StatusCode, Url};
+
+use serde::{Deserialize, Serialize};
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::dump::HtmlDump;
+use crate::instance::Instance;
+
+pub(crate) fn default_user_agent() -> String {
+    format!("handins-cli/{}", env!("CARGO_PKG_VERSION"))
+}
+
+// how the connection to handins should behave, gathered from flags and config
+pub(crate) struct ClientOptions {
+    pub(crate) rate_limit: f64,
+    pub(crate) user_agent: String,
+    pub(crate) log_http: bool,
+    // parse failures are errors rather than skipped rows
+    pub(crate) strict: bool,
+    // which account from the config we're logging in as, if not the default;
+    // anything cached per account is kept apart by this
+    pub(crate) profile: Option<String>,
+    // if set, every response body is also saved here
+    pub(crate) dump_dir: Option<PathBuf>,
+    // if set, every page is saved here with its secrets scrubbed out
+    pub(crate) html_dump: Option<PathBuf>,
+    // print how long each step took at the end of the run
+    pub(crate) timings: bool,
+}
+
+// a logged-in (or logging-in) connection to handins. every request the tool
+// makes goes through here, so that cross-cutting behavior like rate limiting,
+// logging and headers lives in one place
+pub(crate) struct Handins {
+    client: Client,
+    instance: Box<dyn Instance>,
+    limiter: RateLimiter,
+    http_log: Option<HttpLog>,
+    html_dump: Option<HtmlDump>,
+    timings: Option<Timings>,
+    strict: bool,
+    profile: Option<String>,
+}
+
+impl Handins {
+    pub fn new(client: Client, instance: Box<dyn Instance>, options: &ClientOptions) -> Handins {
+        let http_log = if options.log_http || options.dump_dir.is_some() {
+            Some(HttpLog {
+                dump_dir: options.dump_dir.clone(),
+                count: AtomicUsize::new(0),
+            })
+        } else {
+            None
+        };
+
+        Handins {
+            client,
+            instance,
+            limiter: RateLimiter::new(options.rate_limit),
+            http_log,
+            html_dump: options.html_dump.clone().map(HtmlDump::new),
+            timings: if options.timings { Some(Timings::default()) } else { None },
+            strict: options.strict,
+            profile: options.profile.clone(),
+        }
+    }
+
+    // which handins we're talking to
+    pub fn instance(&self) -> &dyn Instance {
+        self.instance.as_ref()
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    // requests are sent as if navigated to from the instance's front page
+    // unless a caller says otherwise
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.get_from(url, self.instance.base_url())
+    }
+
+    // like `get`, but as if we followed a link on the `referer` page
+    pub fn get_from<U: IntoUrl>(&self, url: U, referer: &str) -> RequestBuilder {
+        self.client.get(url).header(REFERER, referer)
+    }
+
+    // like `post`, but as if we submitted a form on the `referer` page
+    pub fn post_from<U: IntoUrl>(&self, url: U, referer: &str) -> RequestBuilder {
+        self.client.post(url).header(REFERER, referer)
+    }
+
+    // for --timings; does nothing without it
+    pub fn record(&self, step: &str, took: Duration) {
+        if let Some(timings) = &self.timings {
+            timings.record(step, took);
+        }
+    }
+
+    // like `record`, for a step that doesn't await anything
+    pub fn time<T>(&self, step: &str, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let result = f();
+        self.record(step, started.elapsed());
+        result
+    }
+
+    pub fn print_timings(&self) {
+        if let Some(timings) = &self.timings {
+            timings.print();
+        }
+    }
+
+    // waits for the rate limiter before sending, so nothing (watching,
+    // fetching every course, ...) can hammer the shared server
+    pub async fn send(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        self.send_timed(request).await.map(|(response, _)| response)
+    }
+
+    // like `send`, but also says when the request actually went out, after
+    // any wait for the rate limiter, so timings only count the network
+    async fn send_timed(&self, request: RequestBuilder) -> reqwest::Result<(Response, Instant)> {
+        let request = request.build()?;
+        let method = request.method().clone();
+        let url = request.url().clone();
+
+        self.limiter.acquire().await;
+        let started = Instant::now();
+        let response = self.client.execute(request).await?;
+
+        if self.http_log.is_some() {
+            eprintln!("http: {} {} -> {}", method, url, response.status());
+        }
+
+        Ok((response, started))
+    }
+
+    // sends the request and reads back the whole body as a page of html
+    pub async fn fetch_text(&self, request: RequestBuilder) -> Result<String, NetworkError> {
+        self.fetch_page(request).await.map(|(_, body)| body)
+    }
+
+    // like `fetch_text`, but also returns where we ended up after following
+    // any redirects
+    pub async fn fetch_page(&self, request: RequestBuilder) -> Result<(Url, String), NetworkError> {
+        let (response, started) = self.send_timed(request).await?;
+        let response = check_status(response)?;
+        let url = response.url().clone();
+        let body = response.text().await?;
+        self.record(&format!("fetch {}", url), started.elapsed());
+
+        if let Some(log) = &self.http_log {
+            eprintln!("http:   {} bytes from {}", body.len(), url);
+            log.dump(&body);
+        }
+        if let Some(dump) = &self.html_dump {
+            dump.save(&url, &body);
+        }
+
+        Ok((url, body))
+    }
+
+    // like `fetch_text`, but when we have `cached` validators handins may
+    // answer that the page hasn't changed, which comes back as None. servers
+    // that ignore conditional requests just send the whole page every time
+    pub async fn fetch_if_changed(
+        &self,
+        request: RequestBuilder,
+        cached: Option<&Validators>,
+    ) -> Result<Option<(String, Validators)>, NetworkError> {
+        let mut request = request;
+        // an unchanged page comes back empty, which would leave nothing to
+        // dump, so --dump-html always asks for the whole thing. so does
+        // --strict, since what's cached may have been parsed leniently
+        let cached = cached.filter(|_| self.html_dump.is_none() && !self.strict);
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+
+        let (response, started) = self.send_timed(request).await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            self.record(&format!("fetch {} (unchanged)", response.url()), started.elapsed());
+            return Ok(None);
+        }
+        let response = check_status(response)?;
+
+        let url = response.url().clone();
+        let validators = Validators::from_response(&response);
+        let body = response.text().await?;
+        self.record(&format!("fetch {}", url), started.elapsed());
+
+        if let Some(log) = &self.http_log {
+            eprintln!("http:   {} bytes from {}", body.len(), url);
+            log.dump(&body);
+        }
+        if let Some(dump) = &self.html_dump {
+            dump.save(&url, &body);
+        }
+
+        Ok(Some((body, validators)))
+    }
+}
+
+// a request that didn't get a usable page back, whether it never reached
+// handins or handins answered with an error
+#[derive(Debug)]
+pub(crate) enum NetworkError {
+    Request(reqwest::Error),
+    Status { status: StatusCode, url: Url },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::Request(e) => write!(f, "couldn't reach handins: {}", e),
+            NetworkError::Status { status, url } => write!(f, "handins returned {} for {}", status, url),
+        }
+    }
+}
+
+impl Error for NetworkError {}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(e: reqwest::Error) -> NetworkError {
+        NetworkError::Request(e)
+    }
+}
+
+// an error page would otherwise be scraped as if it were the real thing,
+// and fail somewhere much more confusing
+pub(crate) fn check_status(response: Response) -> Result<Response, NetworkError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(response)
+    } else {
+        Err(NetworkError::Status {
+            status,
+            url: response.url().clone(),
+        })
+    }
+}
+
+// what handins identified a version of a page by, to send back when asking
+// whether it's changed since
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Validators {
+    pub(crate) etag: Option<String>,
+    pub(crate) last_modified: Option<String>,
+}
+
+impl Validators {
+    fn from_response(response: &Response) -> Validators {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned())
+        };
+        Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+struct HttpLog {
+    dump_dir: Option<PathBuf>,
+    count: AtomicUsize,
+}
+
+impl HttpLog {
+    // failing to write a dump shouldn't stop the actual command, so this
+    // only complains about it
+    fn dump(&self, body: &str) {
+        if let Some(dir) = &self.dump_dir {
+            let n = self.count.fetch_add(1, Ordering::SeqCst);
+            let path = dir.join(format!("response-{:03}.html", n));
+
+            match std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, body)) {
+                Ok(()) => eprintln!("http:   saved body to {}", path.display()),
+                Err(e) => eprintln!("http:   couldn't save body to {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+// each step that --timings reports, in the order they finished
+#[derive(Default)]
+struct Timings(SyncMutex<Vec<(String, Duration)>>);
+
+impl Timings {
+    fn record(&self, step: &str, took: Duration) {
+        if let Ok(mut steps) = self.0.lock() {
+            steps.push((step.to_owned(), took));
+        }
+    }
+
+    fn print(&self) {
+        if let Ok(steps) = self.0.lock() {
+            eprintln!("timings:");
+            for (step, took) in steps.iter() {
+                eprintln!("  {:>9.1}ms  {}", took.as_secs_f64() * 1000.0, step);
+            }
+        }
+    }
+}
+
+// a token bucket: allows short bursts of up to `capacity` requests, then
+// settles down to `rate` requests per second
+struct RateLimiter {
+    capacity: f64,
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(rate: f64) -> RateLimiter {
+        let capacity = rate.max(1.0);
+        RateLimiter {
+            capacity,
+            rate,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    async fn acquire(&self) {
+        let mut state = self.state.lock().await;
+        let (ref mut tokens, ref mut last) = *state;
+
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate).min(self.capacity);
+        *last = now;
+
+        // holding the lock while we sleep keeps waiting requests in order
+        if *tokens < 1.0 {
+            let wait = (1.0 - *tokens) / self.rate;
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+            *tokens = 1.0;
+            *last = Instant::now();
+        }
+
+        *tokens -= 1.0;
+    }
+}
@@ -0,0 +1,174 @@
+use std::error::Error;
+use std::sync::Mutex;
+
+// the capture in progress, kept here so that the ctrl-c handler can put
+// stdout back too, instead of exiting with output still in the pipe
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+
+// starts capturing stdout for --clipboard
+pub(crate) fn start() -> Result<(), Box<dyn Error>> {
+    let capture = Capture::start()?;
+    *CAPTURE.lock().unwrap_or_else(|e| e.into_inner()) = Some(capture);
+    Ok(())
+}
+
+// stops capturing, if we were, and returns what was printed in the meantime
+pub(crate) fn finish() -> Option<String> {
+    let capture = CAPTURE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    capture.map(Capture::finish)
+}
+
+// --clipboard: everything the command prints still goes to the terminal, but
+// a copy is kept to put on the clipboard at the end. prompts keep working,
+// since they pass straight through
+#[cfg(unix)]
+struct Capture {
+    saved_stdout: i32,
+    tee: std::thread::JoinHandle<Vec<u8>>,
+}
+
+#[cfg(unix)]
+impl Capture {
+    fn start() -> Result<Capture, Box<dyn Error>> {
+        use std::fs::File;
+        use std::io::{Read, Write};
+        use std::os::unix::io::FromRawFd;
+
+        let mut fds = [0; 2];
+        // safety: pipe fills in two fresh descriptors, and dup/dup2 only
+        // shuffle descriptors this process owns
+        let saved_stdout = unsafe {
+            if libc::pipe(fds.as_mut_ptr()) != 0 {
+                return Err("couldn't capture output for the clipboard".into());
+            }
+            let saved = libc::dup(libc::STDOUT_FILENO);
+            if saved < 0 || libc::dup2(fds[1], libc::STDOUT_FILENO) < 0 {
+                return Err("couldn't capture output for the clipboard".into());
+            }
+            libc::close(fds[1]);
+            saved
+        };
+
+        // safety: each of these is a descriptor we just made and nothing else
+        // will close it
+        let mut pipe = unsafe { File::from_raw_fd(fds[0]) };
+        let mut terminal = unsafe { File::from_raw_fd(libc::dup(saved_stdout)) };
+
+        let tee = std::thread::spawn(move || {
+            let mut captured = vec![];
+            let mut buf = [0; 4096];
+            while let Ok(n) = pipe.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                let _ = terminal.write_all(&buf[..n]);
+                captured.extend_from_slice(&buf[..n]);
+            }
+            captured
+        });
+
+        Ok(Capture { saved_stdout, tee })
+    }
+
+    // puts stdout back, and returns what was printed in the meantime
+    fn finish(self) -> String {
+        use std::io::Write;
+
+        let _ = std::io::stdout().flush();
+        // safety: restoring the descriptor saved in `start` closes the
+        // pipe's last write end, which lets the tee thread finish
+        unsafe {
+            libc::dup2(self.saved_stdout, libc::STDOUT_FILENO);
+            libc::close(self.saved_stdout);
+        }
+
+        let captured = self.tee.join().unwrap_or_default();
+        String::from_utf8_lossy(&captured).into_owned()
+    }
+}
+
+#[cfg(not(unix))]
+struct Capture;
+
+#[cfg(not(unix))]
+impl Capture {
+    fn start() -> Result<Capture, Box<dyn Error>> {
+        Err("--clipboard is only supported on unix platforms".into())
+    }
+
+    fn finish(self) -> String {
+        String::new()
+    }
+}
+
+// the hidden subcommand that holds on to the clipboard after we exit
+pub(crate) const SERVE: &str = "clipboard-serve";
+
+// on x11 and wayland the clipboard lives only as long as the program that
+// set it, so a copy of ourselves is left running in the background to hold
+// it until something else is copied
+#[cfg(target_os = "linux")]
+pub(crate) fn copy(text: &str) -> Result<(), Box<dyn Error>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::{Command, Stdio};
+
+    let mut server = Command::new(std::env::current_exe()?)
+        .arg(SERVE)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // dropped right away, so the server sees the end of the text
+    server
+        .stdin
+        .take()
+        .ok_or("couldn't hand the output to the clipboard")?
+        .write_all(text.as_bytes())?;
+
+    // it answers once it has the clipboard, or says why it couldn't get it
+    let mut answer = String::new();
+    if let Some(stdout) = server.stdout.take() {
+        BufReader::new(stdout).read_line(&mut answer)?;
+    }
+    match answer.trim() {
+        "ok" => Ok(()),
+        "" => Err("the clipboard helper exited without saying why".into()),
+        reason => Err(reason.to_owned().into()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn copy(text: &str) -> Result<(), Box<dyn Error>> {
+    arboard::Clipboard::new()?.set_text(text)?;
+    Ok(())
+}
+
+// `clipboard-serve`: reads the text from stdin, says whether it got the
+// clipboard, and then holds it
+#[cfg(target_os = "linux")]
+pub(crate) fn serve() -> Result<(), Box<dyn Error>> {
+    use std::io::{Read, Write};
+
+    use arboard::SetExtLinux;
+
+    let mut text = String::new();
+    std::io::stdin().read_to_string(&mut text)?;
+
+    let mut clipboard = match arboard::Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            println!("{}", e);
+            return Ok(());
+        }
+    };
+    println!("ok");
+    std::io::stdout().flush()?;
+
+    clipboard.set().wait().text(text)?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn serve() -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
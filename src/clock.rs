@@ -0,0 +1,33 @@
+use std::sync::OnceLock;
+
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// the timezone deadlines are shown in. normally that's the machine's own,
+// but --timezone / $HANDINS_TZ can override it for when the system timezone
+// is wrong, e.g. while traveling. late checks compare instants, so they come
+// out the same in any timezone
+static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+// takes an IANA name like "America/New_York"; only the first call counts
+pub(crate) fn set_timezone(name: &str) -> Result<(), String> {
+    let tz = name
+        .trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("{} isn't a timezone; use a name like America/New_York", name))?;
+    let _ = TIMEZONE.set(tz);
+    Ok(())
+}
+
+pub(crate) fn now() -> DateTime<FixedOffset> {
+    local(Utc::now().into())
+}
+
+// `time` as it reads on the clock in the chosen timezone
+pub(crate) fn local(time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    let offset = match TIMEZONE.get() {
+        Some(tz) => tz.offset_from_utc_datetime(&time.naive_utc()).fix(),
+        None => Local.offset_from_utc_datetime(&time.naive_utc()).fix(),
+    };
+    time.with_timezone(&offset)
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::Deserialize;
+
+use crate::instance::normalize_course;
+use crate::paths;
+
+// what the instance given by $HANDINS_BASE_URL is called
+const ENV_INSTANCE: &str = "env";
+
+// --ignore-bad-config: a config file that doesn't parse is treated as
+// missing, with one warning, instead of stopping every command
+static IGNORE_BAD: AtomicBool = AtomicBool::new(false);
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_ignore_bad(ignore: bool) {
+    IGNORE_BAD.store(ignore, Ordering::Relaxed);
+}
+
+// settings read from the user's config file, e.g.
+//
+//     [aliases]
+//     hw3 = "Assignment 3: Binary Trees"
+//
+// every section is optional, and a missing file is the same as an empty one
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct Config {
+    // short names that resolve to an exact assignment name on submit
+    #[serde(default)]
+    pub(crate) aliases: HashMap<String, String>,
+
+    // sent as the User-Agent on every request, unless --user-agent is given
+    pub(crate) user_agent: Option<String>,
+
+    // per-course grading categories, keyed by course then category name:
+    //
+    //     [categories.cs2510.homework]
+    //     weight = 40
+    //     patterns = ["Assignment *", "Lab *"]
+    #[serde(default)]
+    pub(crate) categories: HashMap<String, HashMap<String, Category>>,
+
+    // assignments that can be submitted over and over, like practice
+    // problems with an autograder, where `*` matches anything:
+    //
+    //     resubmit = ["Practice *", "Lab * Warmup"]
+    #[serde(default)]
+    pub(crate) resubmit: Vec<String>,
+
+    // the file extension each course expects submissions to have, e.g.
+    //
+    //     [extensions]
+    //     cs2500 = "rkt"
+    #[serde(default)]
+    pub(crate) extensions: HashMap<String, String>,
+
+    // accounts to pick between with --profile, for people with more than one
+    //
+    //     [profiles.ta]
+    //     username = "lyon.sa-ta"
+    #[serde(default)]
+    pub(crate) profiles: HashMap<String, Profile>,
+
+    // a course's local test runner, for `check-local`:
+    //
+    //     [checks.cs2500]
+    //     command = "raco test {file}"
+    //     pass_pattern = "tests passed"
+    #[serde(default)]
+    pub(crate) checks: HashMap<String, Check>,
+
+    // which handins to talk to, unless --instance is given
+    pub(crate) instance: Option<String>,
+
+    // who to log in as when no --profile is given
+    pub(crate) username: Option<String>,
+
+    // the course to use when none is named and there's no .handins file
+    pub(crate) course: Option<String>,
+
+    // how to print results when there's no --format
+    pub(crate) format: Option<String>,
+
+    // settings that only apply to one course, on top of the ones above:
+    //
+    //     [courses.cs4500]
+    //     format = "table"
+    //     weight_tolerance = 10
+    //     hours = 4
+    #[serde(default)]
+    pub(crate) courses: HashMap<String, CourseConfig>,
+
+    // handins deployments other than the built-in one, keyed by name
+    #[serde(default)]
+    pub(crate) instances: HashMap<String, InstanceConfig>,
+}
+
+// per-course overrides; anything left out falls back to the global setting
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct CourseConfig {
+    pub(crate) format: Option<String>,
+    // see `grade --weight-tolerance`
+    pub(crate) weight_tolerance: Option<f64>,
+    // what `submit` reports when there's no --hours
+    pub(crate) hours: Option<f64>,
+}
+
+// a group of assignments worth a fixed share of the course grade
+#[derive(Debug, Deserialize)]
+pub(crate) struct Category {
+    pub(crate) weight: f64,
+    // assignment names in this category, where `*` matches anything
+    pub(crate) patterns: Vec<String>,
+}
+
+// a handins account. passwords aren't kept here; they still come from the
+// usual prompt, $HANDINS_PASSWORD or --password-fd
+#[derive(Debug, Deserialize)]
+pub(crate) struct Profile {
+    pub(crate) username: String,
+}
+
+// how to test a solution before submitting it. `{file}` in the command is
+// replaced with the file being checked, and the check passes when the
+// command exits successfully and, if given, its output contains
+// `pass_pattern`
+#[derive(Debug, Deserialize)]
+pub(crate) struct Check {
+    pub(crate) command: String,
+    pub(crate) pass_pattern: Option<String>,
+}
+
+// another school's handins: where it is and what its courses are called
+#[derive(Debug, Deserialize)]
+pub(crate) struct InstanceConfig {
+    pub(crate) url: String,
+    #[serde(default)]
+    pub(crate) courses: HashMap<String, i64>,
+    // if the login form isn't at /login/
+    pub(crate) login_path: Option<String>,
+}
+
+impl Category {
+    pub fn contains(&self, name: &str) -> bool {
+        self.patterns.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+}
+
+impl Config {
+    // the config file, then $HANDINS_* on top of it. flags are still applied
+    // on top of this by whoever uses the value
+    pub fn load() -> Result<Config, Box<dyn Error>> {
+        let mut config = Config::load_file()?;
+        config.apply_env(|name| std::env::var(name).ok().filter(|value| !value.is_empty()));
+        Ok(config)
+    }
+
+    fn load_file() -> Result<Config, Box<dyn Error>> {
+        let path = match paths::config_file() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        };
+
+        // toml's errors already say which line and column they're about
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()));
+
+        match parsed {
+            Ok(config) => Ok(config),
+            Err(reason) if IGNORE_BAD.load(Ordering::Relaxed) => {
+                if !WARNED.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "warning: ignoring your config at {}, which is invalid: {}",
+                        path.display(),
+                        reason
+                    );
+                }
+                Ok(Config::default())
+            }
+            Err(reason) => Err(format!(
+                "your config at {} is invalid: {} (fix it, or pass --ignore-bad-config to carry on with the defaults)",
+                path.display(),
+                reason
+            )
+            .into()),
+        }
+    }
+
+    // every plain setting can be overridden from the environment, for
+    // containers and CI where writing a config file is awkward. tables like
+    // [aliases] only come from the file
+    fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        if let Some(user_agent) = var("HANDINS_USER_AGENT") {
+            self.user_agent = Some(user_agent);
+        }
+        // a bare url stands in for a whole [instances] entry, with courses
+        // given by their numeric ids
+        if let Some(url) = var("HANDINS_BASE_URL") {
+            self.instances.insert(
+                ENV_INSTANCE.to_owned(),
+                InstanceConfig {
+                    url,
+                    courses: HashMap::new(),
+                    login_path: None,
+                },
+            );
+            self.instance = Some(ENV_INSTANCE.to_owned());
+        }
+        if let Some(instance) = var("HANDINS_INSTANCE") {
+            self.instance = Some(instance);
+        }
+        if let Some(username) = var("HANDINS_USERNAME") {
+            self.username = Some(username);
+        }
+        if let Some(course) = var("HANDINS_COURSE") {
+            self.course = Some(course);
+        }
+        if let Some(format) = var("HANDINS_FORMAT") {
+            self.format = Some(format);
+        }
+    }
+
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name.trim()).map(|target| target.as_str())
+    }
+
+    pub fn categories(&self, course: &str) -> Option<&HashMap<String, Category>> {
+        self.categories.get(&normalize_course(course))
+    }
+
+    pub fn resubmittable(&self, name: &str) -> bool {
+        self.resubmit.iter().any(|pattern| wildcard_match(pattern, name))
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, String> {
+        self.profiles.get(name).ok_or_else(|| {
+            format!("no profile named {}; add it to your config under [profiles.{}]", name, name)
+        })
+    }
+
+    pub fn check(&self, course: &str) -> Option<&Check> {
+        self.checks.get(&normalize_course(course))
+    }
+
+    fn course(&self, course: &str) -> Option<&CourseConfig> {
+        self.courses.get(&normalize_course(course))
+    }
+
+    // a course's own format, if it has one, otherwise the global one
+    pub fn format(&self, course: Option<&str>) -> Option<&str> {
+        course
+            .and_then(|course| self.course(course))
+            .and_then(|course| course.format.as_deref())
+            .or(self.format.as_deref())
+    }
+
+    pub fn weight_tolerance(&self, course: &str) -> Option<f64> {
+        self.course(course).and_then(|course| course.weight_tolerance)
+    }
+
+    pub fn hours(&self, course: &str) -> Option<f64> {
+        self.course(course).and_then(|course| course.hours)
+    }
+
+    pub fn extension(&self, course: &str) -> Option<&str> {
+        self.extensions
+            .get(&normalize_course(course))
+            .map(|ext| ext.as_str())
+    }
+}
+
+// case-insensitive matching where `*` stands for any run of characters
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+
+    // classic greedy match with backtracking to the last `*`
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
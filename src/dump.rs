@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use reqwest::Url;
+
+// --dump-html: a copy of every page fetched, for attaching to a bug report
+// when the scraper breaks on a course the maintainers can't log in to
+pub(crate) struct HtmlDump {
+    dir: PathBuf,
+    count: AtomicUsize,
+}
+
+impl HtmlDump {
+    pub fn new(dir: PathBuf) -> HtmlDump {
+        HtmlDump {
+            dir,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    // like the http log, a dump that can't be written only gets a complaint
+    pub fn save(&self, url: &Url, body: &str) {
+        let n = self.count.fetch_add(1, Ordering::SeqCst);
+        let path = self.dir.join(format!("{:03}-{}.html", n, file_name(url)));
+
+        match std::fs::create_dir_all(&self.dir).and_then(|_| std::fs::write(&path, scrub(body))) {
+            Ok(()) => eprintln!("saved {} to {}", url, path.display()),
+            Err(e) => eprintln!("couldn't save {} to {}: {}", url, path.display(), e),
+        }
+    }
+}
+
+// `courses/129/assignments/` becomes `courses_129_assignments`
+fn file_name(url: &Url) -> String {
+    let mut path = url.path().to_owned();
+    if let Some(query) = url.query() {
+        path.push('_');
+        path.push_str(query);
+    }
+
+    let name: String = path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    let name: String = name.trim_matches('_').chars().take(80).collect();
+
+    if name.is_empty() {
+        "index".to_owned()
+    } else {
+        name
+    }
+}
+
+// anything in a tag that mentions one of these has its values blanked out.
+// session cookies travel in headers, so they never end up in a page
+const SECRETS: [&str; 3] = ["csrf-token", "authenticity_token", "password"];
+
+// attributes that carry the secret itself
+const SECRET_ATTRS: [&str; 2] = ["content=\"", "value=\""];
+
+fn scrub(body: &str) -> String {
+    let mut scrubbed = String::with_capacity(body.len());
+    let mut rest = body;
+
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        scrubbed.push_str(&rest[..start]);
+
+        let tag = &rest[start..end];
+        if SECRETS.iter().any(|secret| tag.contains(secret)) {
+            scrubbed.push_str(&scrub_tag(tag));
+        } else {
+            scrubbed.push_str(tag);
+        }
+        rest = &rest[end..];
+    }
+
+    scrubbed.push_str(rest);
+    scrubbed
+}
+
+fn scrub_tag(tag: &str) -> String {
+    let mut tag = tag.to_owned();
+    for attr in &SECRET_ATTRS {
+        if let Some(start) = tag.find(attr) {
+            let start = start + attr.len();
+            if let Some(len) = tag[start..].find('"') {
+                tag.replace_range(start..start + len, "[scrubbed]");
+            }
+        }
+    }
+    tag
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, FixedOffset};
+
+use serde::{Deserialize, Serialize};
+
+use crate::assignment::Assignment;
+
+// bump this whenever the layout of an export changes, so older snapshots
+// can still be recognized (and rejected, or migrated) when read back in
+pub(crate) const SCHEMA_VERSION: u32 = 1;
+
+// a full snapshot of everything scraped for a single course
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct Export {
+    pub(crate) schema_version: u32,
+    // matches the `kind` of `--format json` output; older exports lack it
+    #[serde(default = "export_kind")]
+    pub(crate) kind: String,
+    pub(crate) course: String,
+    pub(crate) course_id: i64,
+    pub(crate) exported_at: DateTime<FixedOffset>,
+    pub(crate) assignments: Vec<Assignment>,
+}
+
+impl Export {
+    pub fn new(
+        course: String,
+        course_id: i64,
+        exported_at: DateTime<FixedOffset>,
+        assignments: Vec<Assignment>,
+    ) -> Export {
+        Export {
+            schema_version: SCHEMA_VERSION,
+            kind: export_kind(),
+            course,
+            course_id,
+            exported_at,
+            assignments,
+        }
+    }
+}
+
+fn export_kind() -> String {
+    "export".to_owned()
+}
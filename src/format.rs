@@ -0,0 +1,611 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+
+use comfy_table::presets::{NOTHING, UTF8_FULL};
+use comfy_table::{CellAlignment, ContentArrangement, Table};
+
+use crate::assignment::Assignment;
+use crate::clock;
+use crate::grade::GradeSummary;
+use crate::output;
+
+// how listings of assignments get rendered. plain stays the default, since
+// it's the easiest to pipe into other tools
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Format {
+    Plain,
+    Table,
+    Markdown,
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Format, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(Format::Plain),
+            "table" => Ok(Format::Table),
+            "markdown" | "md" => Ok(Format::Markdown),
+            "json" => Ok(Format::Json),
+            _ => Err("unknown output format! supported formats: plain, table, markdown, json"),
+        }
+    }
+}
+
+pub(crate) const FORMATS: [&str; 4] = ["plain", "table", "markdown", "json"];
+
+pub(crate) fn print_grades(
+    format: Format,
+    assignments: &[Assignment],
+    dropped: &[&Assignment],
+    summary: GradeSummary,
+    fields: SummaryFields,
+) {
+    match format {
+        Format::Plain => print_grades_plain(assignments, summary, fields),
+        Format::Table => print_grades_table(assignments, summary, fields),
+        Format::Markdown => print_grades_markdown(assignments, summary, fields),
+        Format::Json => {
+            let (current_grade, minimum_grade, maximum_grade, points_left) = summary;
+            output::print_json(
+                "grades",
+                output::Grades {
+                    current_grade,
+                    minimum_grade,
+                    maximum_grade,
+                    points_left,
+                    assignments,
+                    dropped,
+                },
+            )
+        }
+    }
+}
+
+// which lines of the grade summary to print, for `grade --summary-fields`.
+// json always has all of them
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SummaryFields {
+    current: bool,
+    minimum: bool,
+    maximum: bool,
+    points_left: bool,
+}
+
+pub(crate) const SUMMARY_FIELDS: [&str; 4] = ["current", "min", "max", "points"];
+
+impl SummaryFields {
+    pub fn all() -> SummaryFields {
+        SummaryFields {
+            current: true,
+            minimum: true,
+            maximum: true,
+            points_left: true,
+        }
+    }
+
+    // names from SUMMARY_FIELDS; clap has already rejected anything else
+    pub fn from_names<'a>(names: impl Iterator<Item = &'a str>) -> SummaryFields {
+        let mut fields = SummaryFields {
+            current: false,
+            minimum: false,
+            maximum: false,
+            points_left: false,
+        };
+        for name in names {
+            match name {
+                "current" => fields.current = true,
+                "min" => fields.minimum = true,
+                "max" => fields.maximum = true,
+                "points" => fields.points_left = true,
+                _ => {}
+            }
+        }
+        fields
+    }
+
+    fn rows(&self, summary: GradeSummary) -> Vec<(&'static str, f64)> {
+        let (cur_grade, min_grade, max_grade, max_points) = summary;
+        let rows = [
+            (self.current, "Your current grade", cur_grade),
+            (self.minimum, "Your minimum grade", min_grade),
+            (self.maximum, "Your maximum grade", max_grade),
+            (self.points_left, "Ungraded points you can earn", max_points),
+        ];
+        rows.iter()
+            .filter(|(shown, _, _)| *shown)
+            .map(|&(_, label, value)| (label, value))
+            .collect()
+    }
+}
+
+pub(crate) fn print_ungraded(format: Format, assignments: &[&Assignment]) {
+    match format {
+        Format::Plain => print_ungraded_plain(assignments),
+        Format::Table => print_ungraded_table(assignments),
+        Format::Markdown => print_ungraded_markdown(assignments),
+        Format::Json => output::print_json("ungraded", output::Assignments { assignments }),
+    }
+}
+
+// `grade --sections`: graded and ungraded assignments under their own
+// headers, with the summary last, all from the one fetch
+pub(crate) fn print_sections(
+    format: Format,
+    assignments: &[Assignment],
+    dropped: &[&Assignment],
+    summary: GradeSummary,
+    fields: SummaryFields,
+) {
+    let ungraded: Vec<&Assignment> = assignments.iter().filter(|a| !a.graded()).collect();
+
+    match format {
+        Format::Plain => {
+            let width = name_width(assignments.iter().map(|s| s.name.len()), GRADED_COLUMNS);
+            println!("Graded");
+            print_graded_plain(assignments, width);
+            println!();
+            println!("Ungraded");
+            print_ungraded_plain(&ungraded);
+            println!();
+            print_summary_plain(summary, fields, width);
+        }
+        Format::Table => {
+            println!("Graded");
+            print_graded_table(assignments);
+            println!("Ungraded");
+            print_ungraded_table(&ungraded);
+            print_summary_table(summary, fields);
+        }
+        Format::Markdown => {
+            println!("## Graded");
+            println!();
+            print_graded_markdown(assignments);
+            println!();
+            println!("## Ungraded");
+            println!();
+            print_ungraded_markdown(&ungraded);
+            println!();
+            println!("## Summary");
+            println!();
+            print_summary_markdown(summary, fields);
+        }
+        Format::Json => {
+            let graded: Vec<&Assignment> = assignments.iter().filter(|a| a.graded()).collect();
+            let (current_grade, minimum_grade, maximum_grade, points_left) = summary;
+            output::print_json(
+                "sections",
+                output::Sections {
+                    current_grade,
+                    minimum_grade,
+                    maximum_grade,
+                    points_left,
+                    graded: &graded,
+                    ungraded: &ungraded,
+                    dropped,
+                },
+            )
+        }
+    }
+}
+
+pub(crate) fn print_assignments(format: Format, assignments: &[&Assignment]) {
+    match format {
+        Format::Plain => print_assignments_plain(assignments),
+        Format::Table => print_assignments_table(assignments),
+        Format::Markdown => print_assignments_markdown(assignments),
+        Format::Json => output::print_json("assignments", output::Assignments { assignments }),
+    }
+}
+
+fn print_grades_plain(assignments: &[Assignment], summary: GradeSummary, fields: SummaryFields) {
+    let width = name_width(assignments.iter().map(|s| s.name.len()), GRADED_COLUMNS);
+    print_graded_plain(assignments, width);
+    print_summary_plain(summary, fields, width);
+}
+
+fn print_graded_plain(assignments: &[Assignment], width: usize) {
+    println!(
+        "{:<width$} {:<8} {:>8}",
+        "Homework",
+        "Grades",
+        "Weight",
+        width = width + 5
+    );
+
+    for assignment in assignments {
+        let fmt_name = format!("{}:", fit(&assignment.name, width));
+
+        if let Some(grade) = assignment.grade {
+            println!(
+                "{:<width$} {:<8.2} {:>8.2}",
+                fmt_name,
+                grade,
+                assignment.weight,
+                width = width + 5
+            );
+        }
+    }
+}
+
+fn print_summary_plain(summary: GradeSummary, fields: SummaryFields, width: usize) {
+    for (label, value) in fields.rows(summary) {
+        println!(
+            "{:<width$} {:<.2}",
+            format!("{}:", label),
+            value,
+            width = width + 5
+        );
+    }
+}
+
+fn print_grades_table(assignments: &[Assignment], summary: GradeSummary, fields: SummaryFields) {
+    print_graded_table(assignments);
+    print_summary_table(summary, fields);
+}
+
+fn print_graded_table(assignments: &[Assignment]) {
+    let mut grades = new_table(&["Homework", "Grade", "Weight"]);
+    for assignment in assignments {
+        if let Some(grade) = assignment.grade {
+            grades.add_row(vec![
+                assignment.name.clone(),
+                format!("{:.2}", grade),
+                format!("{:.2}", assignment.weight),
+            ]);
+        }
+    }
+    align_right(&mut grades, &[1, 2]);
+    println!("{}", grades);
+}
+
+fn print_summary_table(summary: GradeSummary, fields: SummaryFields) {
+    let mut totals = new_table(&["Summary", ""]);
+    for (label, value) in fields.rows(summary) {
+        totals.add_row(vec![label.to_owned(), format!("{:.2}", value)]);
+    }
+    align_right(&mut totals, &[1]);
+    println!("{}", totals);
+}
+
+fn print_ungraded_plain(assignments: &[&Assignment]) {
+    let width = name_width(assignments.iter().map(|a| a.name.len()), UNGRADED_COLUMNS);
+
+    println!(
+        "{:<width$} {:<8}",
+        "Assignment",
+        "Weight",
+        width = width + 5
+    );
+
+    for assignment in assignments {
+        println!(
+            "{:<width$} {:<.2}",
+            fit(&assignment.name, width),
+            assignment.weight,
+            width = width + 5
+        );
+    }
+}
+
+fn print_ungraded_table(assignments: &[&Assignment]) {
+    let mut table = new_table(&["Assignment", "Weight"]);
+    for assignment in assignments {
+        table.add_row(vec![
+            assignment.name.clone(),
+            format!("{:.2}", assignment.weight),
+        ]);
+    }
+    align_right(&mut table, &[1]);
+    println!("{}", table);
+}
+
+fn print_assignments_plain(assignments: &[&Assignment]) {
+    let width = name_width(assignments.iter().map(|a| a.name.len()), ASSIGNMENTS_COLUMNS);
+
+    println!(
+        "{:<width$} {:>8} {:>8}   Due",
+        "Assignment",
+        "Grade",
+        "Weight",
+        width = width + 5
+    );
+
+    for assignment in assignments {
+        println!(
+            "{:<width$} {:>8} {:>8.2}   {}",
+            fit(&assignment.name, width),
+            fmt_grade(assignment),
+            assignment.weight,
+            fmt_due_date(assignment),
+            width = width + 5
+        );
+    }
+}
+
+fn print_assignments_table(assignments: &[&Assignment]) {
+    let mut table = new_table(&["Assignment", "Grade", "Weight", "Due"]);
+    for assignment in assignments {
+        table.add_row(vec![
+            assignment.name.clone(),
+            fmt_grade(assignment),
+            format!("{:.2}", assignment.weight),
+            fmt_due_date(assignment),
+        ]);
+    }
+    align_right(&mut table, &[1, 2]);
+    println!("{}", table);
+}
+
+fn print_grades_markdown(assignments: &[Assignment], summary: GradeSummary, fields: SummaryFields) {
+    print_graded_markdown(assignments);
+    println!();
+    print_summary_markdown(summary, fields);
+}
+
+fn print_graded_markdown(assignments: &[Assignment]) {
+    println!("| Homework | Grade | Weight |");
+    println!("| --- | ---: | ---: |");
+    for assignment in assignments {
+        if let Some(grade) = assignment.grade {
+            println!(
+                "| {} | {:.2} | {:.2} |",
+                escape_markdown(&assignment.name),
+                grade,
+                assignment.weight
+            );
+        }
+    }
+}
+
+fn print_summary_markdown(summary: GradeSummary, fields: SummaryFields) {
+    for (label, value) in fields.rows(summary) {
+        println!("- {}: {:.2}", label, value);
+    }
+}
+
+fn print_ungraded_markdown(assignments: &[&Assignment]) {
+    println!("| Assignment | Weight |");
+    println!("| --- | ---: |");
+    for assignment in assignments {
+        println!(
+            "| {} | {:.2} |",
+            escape_markdown(&assignment.name),
+            assignment.weight
+        );
+    }
+}
+
+fn print_assignments_markdown(assignments: &[&Assignment]) {
+    println!("| Assignment | Grade | Weight | Due |");
+    println!("| --- | ---: | ---: | --- |");
+    for assignment in assignments {
+        println!(
+            "| {} | {} | {:.2} | {} |",
+            escape_markdown(&assignment.name),
+            fmt_grade(assignment),
+            assignment.weight,
+            fmt_due_date(assignment)
+        );
+    }
+}
+
+// a pipe in a name would otherwise end the table cell early
+fn escape_markdown(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+fn fmt_grade(assignment: &Assignment) -> String {
+    match assignment.grade {
+        Some(grade) => format!("{:.2}", grade),
+        None => "-".to_owned(),
+    }
+}
+
+pub(crate) fn fmt_due_date(assignment: &Assignment) -> String {
+    match assignment.due_date {
+        Some(due_date) => clock::local(due_date).format("%Y-%m-%d %H:%M").to_string(),
+        None => "-".to_owned(),
+    }
+}
+
+// --plain: nothing but text in fixed columns, and no durations like "in
+// 2d 3h" that change from one run to the next, for screen readers and
+// terminals that can't draw boxes
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_plain(plain: bool) {
+    PLAIN.store(plain, Ordering::Relaxed);
+}
+
+pub(crate) fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+// --max-width, or the terminal's width when printing to one. 0 means no
+// limit, which is what pipes get
+static MAX_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn set_max_width(width: Option<usize>) {
+    MAX_WIDTH.store(width.unwrap_or(0), Ordering::Relaxed);
+}
+
+fn max_width() -> Option<usize> {
+    Some(MAX_WIDTH.load(Ordering::Relaxed)).filter(|&width| width > 0)
+}
+
+// how many columns the plain listings print after the name column
+const GRADED_COLUMNS: usize = 18;
+const UNGRADED_COLUMNS: usize = 9;
+const ASSIGNMENTS_COLUMNS: usize = 37;
+
+// names are never cut shorter than this, even if the row has to wrap
+const MIN_NAME_WIDTH: usize = 10;
+
+// the width of the name column: the longest name, unless that would push
+// the row past the max width
+fn name_width(names: impl Iterator<Item = usize>, rest: usize) -> usize {
+    let longest = names.max().unwrap_or(0);
+    match max_width() {
+        Some(max) => longest.min(max.saturating_sub(rest + 5).max(MIN_NAME_WIDTH)),
+        None => longest,
+    }
+}
+
+fn fit(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_owned();
+    }
+    let mut fitted: String = name.chars().take(width.saturating_sub(1)).collect();
+    fitted.push('…');
+    fitted
+}
+
+fn new_table(header: &[&str]) -> Table {
+    let mut table = Table::new();
+    if plain() {
+        // columns padded with spaces, never rewrapped to the terminal width
+        table
+            .load_preset(NOTHING)
+            .set_content_arrangement(ContentArrangement::Disabled);
+    } else {
+        table
+            .load_preset(UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        if let Some(width) = max_width() {
+            table.set_width(width.min(u16::MAX as usize) as u16);
+        }
+    }
+    table.set_header(header.to_vec());
+    table
+}
+
+fn align_right(table: &mut Table, columns: &[usize]) {
+    for &i in columns {
+        if let Some(column) = table.column_mut(i) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+}
+
+// a user-supplied line like `{name}: {grade}`, printed once per assignment.
+// `{{` and `}}` stand for literal braces
+pub(crate) struct Template(Vec<Piece>);
+
+enum Piece {
+    Literal(String),
+    Field(Field),
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Name,
+    Id,
+    Grade,
+    Weight,
+    Due,
+}
+
+pub(crate) const TEMPLATE_FIELDS: [&str; 5] = ["name", "id", "grade", "weight", "due"];
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Template, Self::Err> {
+        let mut pieces = vec![];
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err("unmatched { in template; write {{ for a literal brace".to_owned()),
+                        }
+                    }
+                    let field = match name.trim() {
+                        "name" => Field::Name,
+                        "id" => Field::Id,
+                        "grade" => Field::Grade,
+                        "weight" => Field::Weight,
+                        "due" => Field::Due,
+                        _ => {
+                            return Err(format!(
+                                "unknown template field {{{}}}! supported fields: {}",
+                                name,
+                                TEMPLATE_FIELDS.join(", ")
+                            ))
+                        }
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field(field));
+                }
+                '}' => return Err("unmatched } in template; write }} for a literal brace".to_owned()),
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Template(pieces))
+    }
+}
+
+impl Template {
+    pub fn render(&self, assignment: &Assignment) -> String {
+        self.0
+            .iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => text.clone(),
+                Piece::Field(Field::Name) => assignment.name.clone(),
+                Piece::Field(Field::Id) => assignment.id.to_string(),
+                Piece::Field(Field::Grade) => fmt_grade(assignment),
+                Piece::Field(Field::Weight) => assignment.weight.to_string(),
+                Piece::Field(Field::Due) => fmt_due_date(assignment),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str) -> Result<String, String> {
+        let assignment = Assignment::new("Lab 1".to_owned(), 7, Some(92.5), 10.0, None);
+        Ok(template.parse::<Template>()?.render(&assignment))
+    }
+
+    #[test]
+    fn templates_fill_in_fields() {
+        assert_eq!(render("{id}: {name} ({weight}%)").unwrap(), "7: Lab 1 (10%)");
+        assert_eq!(render("{{{ name }}}").unwrap(), "{Lab 1}");
+    }
+
+    #[test]
+    fn templates_reject_bad_braces() {
+        assert_eq!(
+            render("{name").unwrap_err(),
+            "unmatched { in template; write {{ for a literal brace"
+        );
+        assert!(render("name}").unwrap_err().starts_with("unmatched }"));
+        assert!(render("{score}").unwrap_err().starts_with("unknown template field {score}"));
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use crate::assignment::Assignment;
+use crate::config::Category;
+
+// the four numbers `calculate_grade` produces, in the order it produces them:
+// current grade, minimum grade, maximum grade, and ungraded points you can earn
+pub(crate) type GradeSummary = (f64, f64, f64, f64);
+
+// the `drop_lowest` lowest graded assignments, for courses that drop a few
+// homeworks. ties go to whichever was due first, and at least one graded
+// assignment is always kept so there's still a grade to compute
+pub(crate) fn lowest_graded(assignments: &[Assignment], drop_lowest: usize) -> Vec<&Assignment> {
+    let mut graded: Vec<&Assignment> = assignments.iter().filter(|a| a.graded()).collect();
+    graded.sort_by(|a1, a2| {
+        a1.grade
+            .partial_cmp(&a2.grade)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a1.due_date.cmp(&a2.due_date))
+    });
+    graded.truncate(drop_lowest.min(graded.len().saturating_sub(1)));
+    graded
+}
+
+pub(crate) fn calculate_grade(assignments: &[Assignment], drop_lowest: usize) -> GradeSummary {
+    let dropped: Vec<i64> = lowest_graded(assignments, drop_lowest)
+        .iter()
+        .map(|a| a.id)
+        .collect();
+    let dropped_weight: f64 = assignments
+        .iter()
+        .filter(|a| dropped.contains(&a.id))
+        .map(|a| a.weight)
+        .sum();
+    let assignments: Vec<&Assignment> = assignments
+        .iter()
+        .filter(|a| !dropped.contains(&a.id))
+        .collect();
+
+    // dropped assignments don't count towards the course at all
+    let course_weight = 100.0 - dropped_weight;
+
+    let valid_weights: Vec<f64> = assignments
+        .iter()
+        .filter_map(|a| a.grade.map(|_| a.weight))
+        .collect();
+
+    let future_weights: Vec<f64> = assignments
+        .iter()
+        .filter_map(|a| match a.grade {
+            Some(_) => None,
+            None => Some(a.weight),
+        })
+        .collect();
+
+    let total_weight: f64 = valid_weights.iter().sum();
+
+    let grades: Vec<f64> = assignments.iter().filter_map(|a| a.grade).collect();
+
+    let scaled_grade = grades
+        .iter()
+        .zip(valid_weights.iter())
+        .fold(0.0, |sum, grade_pair| grade_pair.0 * grade_pair.1 + sum);
+
+    let future_weight: f64 = future_weights.iter().sum();
+    let optimistic_grade = scaled_grade + 100.0 * (course_weight - total_weight);
+
+    (
+        scaled_grade / total_weight,        // your current grade
+        scaled_grade / course_weight,       // your minimum grade
+        optimistic_grade / course_weight,   // maximum possible grade for the course
+        // most points you can earn from ungraded assignments
+        // delta (max possible grade from ungraded assignments, current grade)
+        (scaled_grade + 100.0 * future_weight) / (total_weight + future_weight)
+            - (scaled_grade / total_weight),
+    )
+}
+
+// how one grading category is doing so far
+#[derive(Debug)]
+pub(crate) struct CategoryGrade<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) weight: f64,
+    // weighted average of the graded assignments in the category, if any are
+    pub(crate) average: Option<f64>,
+    pub(crate) graded: usize,
+    pub(crate) total: usize,
+}
+
+// splits assignments into their categories and averages each one, using the
+// assignments' own weights within a category. also returns anything that
+// didn't fall into a category, which can't count towards the grade
+pub(crate) fn category_grades<'a, 'b>(
+    assignments: &'b [Assignment],
+    categories: &'a HashMap<String, Category>,
+) -> (Vec<CategoryGrade<'a>>, Vec<&'b Assignment>) {
+    let mut names: Vec<&String> = categories.keys().collect();
+    names.sort();
+
+    let mut grades = vec![];
+    for name in names {
+        let category = &categories[name];
+        let members: Vec<&Assignment> = assignments
+            .iter()
+            .filter(|a| category.contains(&a.name))
+            .collect();
+
+        let graded: Vec<&&Assignment> = members.iter().filter(|a| a.graded()).collect();
+        let weight: f64 = graded.iter().map(|a| a.weight).sum();
+        let points: f64 = graded
+            .iter()
+            .filter_map(|a| a.grade.map(|grade| grade * a.weight))
+            .sum();
+
+        grades.push(CategoryGrade {
+            name,
+            weight: category.weight,
+            average: if weight > 0.0 { Some(points / weight) } else { None },
+            graded: graded.len(),
+            total: members.len(),
+        });
+    }
+
+    let uncategorized = assignments
+        .iter()
+        .filter(|a| !categories.values().any(|category| category.contains(&a.name)))
+        .collect();
+
+    (grades, uncategorized)
+}
+
+// the course grade as a weighted sum of category averages, counting only
+// categories that have something graded in them yet
+pub(crate) fn category_weighted_grade(grades: &[CategoryGrade]) -> Option<f64> {
+    let weight: f64 = grades
+        .iter()
+        .filter(|g| g.average.is_some())
+        .map(|g| g.weight)
+        .sum();
+    let points: f64 = grades
+        .iter()
+        .filter_map(|g| g.average.map(|average| average * g.weight))
+        .sum();
+
+    if weight > 0.0 {
+        Some(points / weight)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use proptest::prelude::*;
+
+    // floating point slop allowed when comparing grades
+    const EPSILON: f64 = 1e-9;
+
+    // a course's assignments: grades out of 100, weights adding up to 100,
+    // and at least one graded so there's a current grade to speak of
+    fn course() -> impl Strategy<Value = Vec<Assignment>> {
+        (
+            (0.0..=100.0f64, 1.0..50.0f64),
+            prop::collection::vec((prop::option::of(0.0..=100.0f64), 1.0..50.0f64), 0..12),
+        )
+            .prop_map(|((grade, weight), rest)| {
+                let mut rows = vec![(Some(grade), weight)];
+                rows.extend(rest);
+                let total: f64 = rows.iter().map(|(_, weight)| weight).sum();
+
+                rows.into_iter()
+                    .enumerate()
+                    .map(|(id, (grade, weight))| {
+                        let name = format!("Assignment {}", id);
+                        Assignment::new(name, id as i64, grade, weight * 100.0 / total, None)
+                    })
+                    .collect()
+            })
+    }
+
+    #[test]
+    fn point_grades_count_as_their_percentage() {
+        let mut adder = Assignment::new("Adder".to_owned(), 1, Some(90.0), 20.0, None);
+        adder.points = Some(crate::assignment::Points {
+            earned: 45.0,
+            possible: 50.0,
+        });
+        let assignments = vec![
+            adder,
+            Assignment::new("Boa".to_owned(), 2, Some(75.0), 20.0, None),
+            Assignment::new("Cobra".to_owned(), 3, None, 60.0, None),
+        ];
+
+        let (current, min, max, _) = calculate_grade(&assignments, 0);
+        assert!((current - 82.5).abs() < EPSILON);
+        assert!((min - 33.0).abs() < EPSILON);
+        assert!((max - 93.0).abs() < EPSILON);
+    }
+
+    proptest! {
+        #[test]
+        fn outputs_are_finite(assignments in course(), drop_lowest in 0..3usize) {
+            let (current, min, max, points) = calculate_grade(&assignments, drop_lowest);
+            prop_assert!(current.is_finite());
+            prop_assert!(min.is_finite());
+            prop_assert!(max.is_finite());
+            prop_assert!(points.is_finite());
+        }
+
+        #[test]
+        fn current_is_between_min_and_max(assignments in course(), drop_lowest in 0..3usize) {
+            let (current, min, max, _) = calculate_grade(&assignments, drop_lowest);
+            prop_assert!(min <= current + EPSILON, "min {} above current {}", min, current);
+            prop_assert!(current <= max + EPSILON, "current {} above max {}", current, max);
+            prop_assert!((0.0..=100.0 + EPSILON).contains(&current));
+        }
+
+        #[test]
+        fn ungraded_assignments_leave_current_alone(assignments in course(), weight in 0.0..50.0f64) {
+            let (current, _, _, _) = calculate_grade(&assignments, 0);
+
+            let mut more = assignments.clone();
+            more.push(Assignment::new("Extra".to_owned(), more.len() as i64, None, weight, None));
+            let (with_extra, _, _, _) = calculate_grade(&more, 0);
+
+            prop_assert!((current - with_extra).abs() < EPSILON);
+        }
+
+        #[test]
+        fn full_marks_on_the_rest_gets_the_max(assignments in course()) {
+            let (_, _, max, _) = calculate_grade(&assignments, 0);
+
+            let perfect: Vec<Assignment> = assignments
+                .iter()
+                .cloned()
+                .map(|mut a| {
+                    a.grade = a.grade.or(Some(100.0));
+                    a
+                })
+                .collect();
+            let (current, min, _, _) = calculate_grade(&perfect, 0);
+
+            prop_assert!((current - max).abs() < EPSILON, "got {}, max was {}", current, max);
+            prop_assert!((min - max).abs() < EPSILON);
+        }
+
+        #[test]
+        fn dropping_the_lowest_never_lowers_the_grade(assignments in course(), drop_lowest in 1..3usize) {
+            let (current, _, _, _) = calculate_grade(&assignments, 0);
+            let (dropped, _, _, _) = calculate_grade(&assignments, drop_lowest);
+            prop_assert!(dropped + EPSILON >= current, "{} dropped to {}", current, dropped);
+        }
+    }
+}
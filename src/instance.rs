@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use reqwest::Url;
+
+use crate::config::{Config, InstanceConfig};
+
+// the bits of talking to handins that change from school to school. the
+// software is the same everywhere, but each deployment lives at its own
+// address and has its own course table
+pub(crate) trait Instance: Send + Sync {
+    // where this handins lives, with a trailing slash
+    fn base_url(&self) -> &str;
+
+    // course id for a course name, if this instance knows the name
+    fn lookup_course(&self, course: &str) -> Option<i64>;
+
+    // one name for each known course, for commands that look at every
+    // course at once
+    fn known_courses(&self) -> Vec<&str>;
+
+    // the login form, relative to the base url
+    fn login_path(&self) -> &str {
+        "login/"
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url(), path.trim_start_matches('/'))
+    }
+
+    fn host(&self) -> Option<String> {
+        Url::parse(self.base_url())
+            .ok()?
+            .host_str()
+            .map(|host| host.to_owned())
+    }
+
+    // a numeric course is taken as the raw handins course id, since course
+    // tables go stale every semester. names are normalized first, and one
+    // that starts with a number is also tried with "cs" in front
+    fn resolve_course(&self, course: &str) -> Option<i64> {
+        let course = normalize_course(course);
+        match course.parse::<i64>() {
+            Ok(id) => Some(id),
+            Err(_) if course.starts_with(|c: char| c.is_ascii_digit()) => self
+                .lookup_course(&course)
+                .or_else(|| self.lookup_course(&format!("cs{}", course))),
+            Err(_) => self.lookup_course(&course),
+        }
+    }
+}
+
+// so that " CS 2510 " and "cs2510" are the same course
+pub(crate) fn normalize_course(course: &str) -> String {
+    course
+        .split_whitespace()
+        .collect::<String>()
+        .to_lowercase()
+}
+
+// the name the built-in instance goes by on the command line and in config
+pub(crate) const DEFAULT_INSTANCE: &str = "neu-ccs";
+
+// handins.ccs.neu.edu, which this tool was written against
+pub(crate) struct NeuCcs;
+
+impl Instance for NeuCcs {
+    fn base_url(&self) -> &str {
+        "https://handins.ccs.neu.edu/"
+    }
+
+    // spring 2021 courses
+    // will probably add a macro to convert a file w course names, number
+    // to a lookup table, if numbers get updated each year
+    fn lookup_course(&self, course: &str) -> Option<i64> {
+        match course.to_lowercase().as_str() {
+            "cs2500" | "fundies1" | "f1" => Some(131),
+            "cs2510" | "fundies2" | "f2" => Some(129),
+            "cs2510a" | "fundies2accel" | "f2accel" | "f2a" => Some(126),
+            "cs3500" | "ood" => Some(138),
+            "cs3500sp21" | "oodsp21" => Some(133),
+            "cs4410" | "compilers" => Some(127),
+            "cs4500" | "swdev" | "swe" => Some(130),
+            _ => None,
+        }
+    }
+
+    fn known_courses(&self) -> Vec<&str> {
+        vec![
+            "cs2500",
+            "cs2510",
+            "cs2510a",
+            "cs3500",
+            "cs3500sp21",
+            "cs4410",
+            "cs4500",
+        ]
+    }
+}
+
+// any other handins, described in the config file:
+//
+//     [instances.example]
+//     url = "https://handins.example.edu/"
+//     courses = { cs101 = 12, cs102 = 15 }
+pub(crate) struct Custom {
+    base_url: String,
+    courses: HashMap<String, i64>,
+    login_path: Option<String>,
+}
+
+impl Custom {
+    fn new(config: &InstanceConfig) -> Custom {
+        let mut base_url = config.url.trim().to_owned();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+
+        Custom {
+            base_url,
+            courses: config
+                .courses
+                .iter()
+                .map(|(name, id)| (normalize_course(name), *id))
+                .collect(),
+            login_path: config.login_path.clone(),
+        }
+    }
+}
+
+impl Instance for Custom {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn lookup_course(&self, course: &str) -> Option<i64> {
+        self.courses.get(&normalize_course(course)).copied()
+    }
+
+    fn known_courses(&self) -> Vec<&str> {
+        let mut courses: Vec<&str> = self.courses.keys().map(|name| name.as_str()).collect();
+        courses.sort_unstable();
+        courses
+    }
+
+    fn login_path(&self) -> &str {
+        self.login_path.as_deref().unwrap_or("login/")
+    }
+}
+
+// --instance wins over the config file's `instance`, and with neither we
+// talk to the built-in one
+pub(crate) fn select(name: Option<&str>, config: &Config) -> Result<Box<dyn Instance>, Box<dyn Error>> {
+    let name = name
+        .or(config.instance.as_deref())
+        .unwrap_or(DEFAULT_INSTANCE);
+
+    if let Some(instance) = config.instances.get(name) {
+        return Ok(Box::new(Custom::new(instance)));
+    }
+
+    match name {
+        DEFAULT_INSTANCE => Ok(Box::new(NeuCcs)),
+        _ => Err(format!(
+            "no handins instance named {}; add it to your config under [instances.{}]",
+            name, name
+        )
+        .into()),
+    }
+}
@@ -1,61 +1,512 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::io;
-use std::io::{ErrorKind, Write};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 
-use clap::{App, Arg, ArgMatches, SubCommand};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 
-use chrono::DateTime;
+use chrono::{DateTime, FixedOffset};
 
-use reqwest::{Client, Url};
+use reqwest::Client;
 use reqwest::cookie::{CookieStore, Jar};
 use reqwest::multipart::{Form, Part};
 
 use select::document::Document;
-use select::predicate::{Attr, Class, Name, Text};
+use select::predicate::{Attr, Name, Predicate, Text};
+
+use serde::Serialize;
 
 use simsearch::SimSearch;
 
 use tokio::io::AsyncReadExt;
 
+use unicode_normalization::UnicodeNormalization;
+
 mod assignment;
+mod cache;
+mod client;
+mod clipboard;
+mod clock;
+mod config;
+mod dump;
+mod export;
+mod format;
+mod grade;
+mod instance;
+mod output;
+mod paths;
+mod project;
+mod scrape;
+mod selftest;
+mod terminal;
 
 use crate::assignment::Assignment;
+use crate::cache::CachedAssignments;
+use crate::client::{ClientOptions, Handins};
+use crate::config::{Category, Config};
+use crate::export::Export;
+use crate::format::{Format, SummaryFields, Template};
+use crate::instance::Instance;
+use crate::scrape::{parse_assignments, require_attr, require_element};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    let matches = App::new("handins")
+async fn main() {
+    let matches = cli().get_matches();
+
+    if let (clipboard::SERVE, Some(_)) = matches.subcommand() {
+        return exit_on_error(clipboard::serve());
+    }
+
+    // stdout stops being the terminal once it is captured, so ask now
+    let terminal_width = terminal::width();
+
+    if matches.is_present("clipboard") {
+        if let Err(e) = clipboard::start() {
+            return exit_on_error(Err(e));
+        }
+    }
+
+    let result = run(&matches, terminal_width).await;
+
+    // only output from a command that worked is worth pasting anywhere
+    if let Some(output) = clipboard::finish() {
+        if result.is_ok() && !output.trim().is_empty() {
+            match clipboard::copy(&output) {
+                Ok(()) => eprintln!("copied the output to the clipboard"),
+                Err(e) => eprintln!("couldn't copy the output to the clipboard: {}", e),
+            }
+        }
+    }
+
+    exit_on_error(result)
+}
+
+// errors are printed as they read, rather than debug-formatted the way
+// returning them from main would, which mangles multi-line ones like
+// --strict's row dumps
+fn exit_on_error(result: Result<(), Box<dyn Error>>) {
+    if let Err(e) = result {
+        match e.downcast_ref::<terminal::Exit>() {
+            Some(terminal::Exit(code)) => std::process::exit(*code),
+            None => {
+                eprintln!("error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+async fn run(matches: &ArgMatches<'_>, terminal_width: Option<usize>) -> Result<(), Box<dyn Error>> {
+    terminal::exit_on_ctrl_c();
+
+    format::set_plain(matches.is_present("plain"));
+    config::set_ignore_bad(matches.is_present("ignore-bad-config"));
+
+    let max_width = matches
+        .value_of("max-width")
+        .map(|width| width.parse::<usize>())
+        .transpose()
+        .map_err(|_| "max width must be a whole number of columns!")?;
+    format::set_max_width(max_width.or(terminal_width));
+
+    if let Some(timezone) = matches.value_of("timezone") {
+        clock::set_timezone(timezone)?;
+    }
+
+    // planning is pure math, so don't make the user log in for it
+    if let ("plan", Some(sub_matches)) = matches.subcommand() {
+        return plan_grades(sub_matches).await;
+    }
+
+    // nor for checking the scrapers against the bundled pages
+    if let ("selftest", Some(_)) = matches.subcommand() {
+        return selftest::run();
+    }
+
+    let password_fd = matches
+        .value_of("password-fd")
+        .map(|fd| fd.parse::<i32>())
+        .transpose()
+        .map_err(|_| "password file descriptor must be a number!")?;
+
+    let rate_limit = matches
+        .value_of("rate-limit")
+        .unwrap_or("2")
+        .parse::<f64>()
+        .ok()
+        .filter(|rate| *rate > 0.0)
+        .ok_or("rate limit must be a positive number of requests per second!")?;
+
+    let config = Config::load()?;
+
+    // listing profiles only needs the config file
+    if let ("profiles", Some(_)) = matches.subcommand() {
+        list_profiles(&config);
+        return Ok(());
+    }
+
+    // and checking a solution locally never talks to handins at all
+    if let ("check-local", Some(sub_matches)) = matches.subcommand() {
+        return check_local(&config, sub_matches).await;
+    }
+
+    let instance = instance::select(matches.value_of("instance"), &config)?;
+    let profile = matches
+        .value_of("profile")
+        .map(|name| config.profile(name).map(|profile| (name, profile)))
+        .transpose()?;
+
+    // fail fast on a mistyped course, before bothering the user for credentials
+    if let (name, Some(sub_matches)) = matches.subcommand() {
+        let mut courses: Vec<String> = ["COURSE", "course", "COURSES"]
+            .iter()
+            .filter_map(|arg| sub_matches.values_of(arg))
+            .flatten()
+            .map(str::to_owned)
+            .collect();
+        // these fall back to a .handins marker or the config's `course`,
+        // which can be mistyped just the same
+        if courses.is_empty() && COURSE_ARG_SUBCOMMANDS.contains(&name) {
+            courses.push(course_arg(sub_matches)?);
+        }
+        for course in &courses {
+            resolve_course(instance.as_ref(), course).map_err(|_| {
+                format!("{} is not a supported course for handins at this time", course)
+            })?;
+        }
+    }
+
+    let dump_dir = if matches.is_present("log-http-bodies") {
+        let dir = paths::cache_dir()
+            .unwrap_or_else(std::env::temp_dir)
+            .join(format!("http-{}", std::process::id()));
+        eprintln!("http: saving response bodies to {}", dir.display());
+        Some(dir)
+    } else {
+        None
+    };
+
+    let options = ClientOptions {
+        rate_limit,
+        user_agent: matches
+            .value_of("user-agent")
+            .map(|agent| agent.to_owned())
+            .or_else(|| config.user_agent.clone())
+            .unwrap_or_else(client::default_user_agent),
+        log_http: matches.is_present("log-http"),
+        strict: matches.is_present("strict"),
+        profile: profile.map(|(name, _)| name.to_owned()),
+        dump_dir,
+        html_dump: matches.value_of("dump-html").map(PathBuf::from),
+        timings: matches.is_present("timings"),
+    };
+
+    // a digest usually runs with nobody watching, so it never prompts
+    let interactive = !matches.is_present("non-interactive") && matches.subcommand_name() != Some("digest");
+    let username = profile
+        .map(|(_, profile)| profile.username.as_str())
+        .or(config.username.as_deref());
+
+    if let ("init", Some(_)) = matches.subcommand() {
+        if !interactive {
+            return Err("init asks questions, so it can't run with --non-interactive")?;
+        }
+        return init(instance, password_fd, &options).await;
+    }
+
+    // using to debug cookie values, if necessary 
+    let started = std::time::Instant::now();
+    let client = handins_login::<Jar>(None, instance, username, password_fd, interactive, &options).await?;
+    client.record("log in, in total", started.elapsed());
+
+    let result = match matches.subcommand() {
+        ("repl", Some(_)) => repl(&client).await,
+        _ => run_subcommand(&client, matches).await,
+    };
+    client.print_timings();
+    result
+}
+
+fn cli() -> App<'static, 'static> {
+    App::new("handins")
 		.version("0.1")
 		.author("Sam Lyon <sam.c.lyon@gmail.com>")
 		.about("Command line interface for handins.ccs.neu.edu")
+		.arg(Arg::with_name("password-fd")
+			.long("password-fd")
+			.help("read your password from the given open file descriptor instead of the terminal")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("log-http")
+			.long("log-http")
+			.help("log every request made to handins (method, url, status, size) to stderr")
+			.global(true))
+		.arg(Arg::with_name("log-http-bodies")
+			.long("log-http-bodies")
+			.help("like --log-http, but also save every response body under the cache directory")
+			.global(true))
+		.arg(Arg::with_name("dump-html")
+			.long("dump-html")
+			.value_name("DIR")
+			.help("save every page fetched into DIR, with tokens scrubbed, to attach to a bug report")
+			.hidden(true)
+			.takes_value(true)
+			.global(true))
+		.arg(Arg::with_name("non-interactive")
+			.short("y")
+			.long("non-interactive")
+			.alias("yes")
+			.help("never prompt: take the safe default for every question, and fail if a required answer (like credentials) isn't available")
+			.global(true))
+		.arg(Arg::with_name("user-agent")
+			.long("user-agent")
+			.help("User-Agent to identify as to handins (defaults to handins-cli/<version>)")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("instance")
+			.long("instance")
+			.value_name("NAME")
+			.help("which handins to use: neu-ccs (the default), or one named under [instances] in your config")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("timezone")
+			.long("timezone")
+			.value_name("TZ")
+			.env("HANDINS_TZ")
+			.help("timezone to display times in, e.g. America/New_York")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("strict")
+			.long("strict")
+			.help("fail on any row of a handins page that can't be parsed, instead of skipping it")
+			.global(true))
+		.arg(Arg::with_name("profile")
+			.long("profile")
+			.value_name("NAME")
+			.help("log in as the account named NAME under [profiles] in your config")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("plain")
+			.long("plain")
+			.help("plain text only: no box drawing, fixed-width columns, and absolute times instead of \"in 2d 3h\"")
+			.global(true))
+		.arg(Arg::with_name("ignore-bad-config")
+			.long("ignore-bad-config")
+			.help("if your config file can't be read, warn and use the defaults instead of stopping")
+			.global(true))
+		.arg(Arg::with_name("clipboard")
+			.long("clipboard")
+			.help("also copy everything the command prints to the clipboard")
+			.global(true))
+		.arg(Arg::with_name("timings")
+			.long("timings")
+			.help("print how long logging in, each request and each parse took, at the end of the run")
+			.global(true))
+		.arg(Arg::with_name("max-width")
+			.long("max-width")
+			.value_name("N")
+			.help("shorten long assignment names so listings fit in N columns [default: the terminal's width]")
+			.global(true)
+			.takes_value(true))
+		.arg(Arg::with_name("rate-limit")
+			.long("rate-limit")
+			.help("maximum number of requests per second to send to handins")
+			.default_value("2")
+			.global(true)
+			.takes_value(true))
 		.subcommand(SubCommand::with_name("grade")
 			.about("fetches your grades for a given course")
 			.version("0.1")
 			.author("Sam Lyon <sam.c.lyon@gmail.com")
 			.arg(Arg::with_name("COURSE")
-				.help("name of the course taken (cs2510, cs2510a)")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id; defaults to the one in a .handins file in or above this directory")
 				.long_help(
 "Name of the course to retrieve grades from. Supports the following courses:\n\
 cs2510   	--		Fundamentals of Computer Science 2\n\
-cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n"
+cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n\
+A numeric handins course id (e.g. 129) is also accepted, and is used as-is.\n\
+If left off, the course named in a .handins file in this directory or above is used.\n"
 				)
-				.required(true)
 				.index(1))
+			.arg(Arg::with_name("weight-tolerance")
+				.long("weight-tolerance")
+				.help("how far (in percent) the total assignment weight may stray from 100 before warning [default: 5]")
+				.takes_value(true))
+			.arg(Arg::with_name("diff")
+				.long("diff")
+				.value_name("OLD_EXPORT")
+				.help("compare against a snapshot previously written by `handins export`")
+				.takes_value(true))
+			.arg(Arg::with_name("whatif")
+				.long("whatif")
+				.value_name("NAME=GRADE,...")
+				.help("pretend ungraded assignments got these grades, and show the projected result")
+				.takes_value(true))
+			.arg(Arg::with_name("metric")
+				.long("metric")
+				.help("only print this one number from the summary, for prompts and scripts")
+				.possible_values(&["current", "min", "max", "ungraded-points"])
+				.conflicts_with_all(&["passing-threshold", "assignment", "template"])
+				.takes_value(true))
+			.arg(Arg::with_name("precision")
+				.long("precision")
+				.value_name("DIGITS")
+				.help("how many decimal places --metric prints [default: 2]")
+				.requires("metric")
+				.takes_value(true))
+			.arg(Arg::with_name("passing-threshold")
+				.long("passing-threshold")
+				.value_name("GRADE")
+				.help("only print whether your current grade is at least GRADE, exiting with 1 if it isn't")
+				.takes_value(true))
+			.arg(Arg::with_name("assignment")
+				.long("assignment")
+				.short("a")
+				.value_name("NAME")
+				.help("only print the grade for the assignment best matching NAME")
+				.takes_value(true))
+			.arg(Arg::with_name("contributions")
+				.long("contributions")
+				.help("show how many points of your current grade each graded assignment contributes"))
+			.arg(Arg::with_name("summary-fields")
+				.long("summary-fields")
+				.value_name("FIELDS")
+				.help("which summary lines to print, comma separated [default: all of them]")
+				.possible_values(&format::SUMMARY_FIELDS)
+				.use_delimiter(true)
+				.takes_value(true))
+			.arg(Arg::with_name("sections")
+				.long("sections")
+				.help("also list ungraded assignments, under separate Graded and Ungraded headers"))
+			.arg(Arg::with_name("explain")
+				.long("explain")
+				.help("show the arithmetic behind each number in the summary"))
+			.arg(Arg::with_name("by-category")
+				.long("by-category")
+				.help("also break your grade down by the grading categories in your config file"))
+			.arg(Arg::with_name("drop-lowest")
+				.long("drop-lowest")
+				.value_name("N")
+				.help("leave your N lowest graded assignments out of the calculation")
+				.takes_value(true))
+			.arg(Arg::with_name("format")
+				.short("f")
+				.long("format")
+				.help("how to print the results")
+				.possible_values(&format::FORMATS)
+				.takes_value(true))
+			.arg(Arg::with_name("template")
+				.long("template")
+				.value_name("TEMPLATE")
+				.help("instead, print one line per assignment following TEMPLATE, e.g. \"{name}: {grade}\". \
+				       fields are {name}, {id}, {grade}, {weight} and {due}")
+				.takes_value(true))
 		)
 		.subcommand(SubCommand::with_name("ungraded")
 			.about("fetches your ungraded assignments for a given course")
 			.version("0.1")
 			.author("Sam Lyon <sam.c.lyon@gmail.com")
 			.arg(Arg::with_name("COURSE")
-				.help("name of the course taken (cs2510, cs2510a)")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id; defaults to the one in a .handins file in or above this directory")
 				.long_help(
 "Name of the course to retrieve grades from. Supports the following courses:\n\
 cs2510   	--		Fundamentals of Computer Science 2\n\
-cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n"
+cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n\
+A numeric handins course id (e.g. 129) is also accepted, and is used as-is.\n\
+If left off, the course named in a .handins file in this directory or above is used.\n"
 				)
+				.index(1))
+			.arg(Arg::with_name("count")
+				.long("count")
+				.help("only print how many assignments are ungraded, for prompts and status bars"))
+			.arg(Arg::with_name("format")
+				.short("f")
+				.long("format")
+				.help("how to print the results")
+				.possible_values(&format::FORMATS)
+				.takes_value(true))
+		)
+		.subcommand(SubCommand::with_name("assignments")
+			.about("lists every assignment for a given course, graded or not")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSE")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id; defaults to the one in a .handins file in or above this directory")
+				.index(1))
+			.arg(Arg::with_name("only-graded")
+				.long("only-graded")
+				.help("only list assignments that have been graded")
+				.conflicts_with("only-ungraded"))
+			.arg(Arg::with_name("only-ungraded")
+				.long("only-ungraded")
+				.help("only list assignments that haven't been graded yet"))
+			.arg(Arg::with_name("format")
+				.short("f")
+				.long("format")
+				.help("how to print the results")
+				.possible_values(&format::FORMATS)
+				.takes_value(true))
+		)
+		.subcommand(SubCommand::with_name("search")
+			.about("fuzzy searches every assignment in a course by name")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSE")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id")
 				.required(true)
 				.index(1))
+			.arg(Arg::with_name("QUERY")
+				.help("(part of) the name of the assignment to look for")
+				.required(true)
+				.index(2))
+			.arg(Arg::with_name("limit")
+				.short("n")
+				.long("limit")
+				.help("how many matches to show")
+				.default_value("5")
+				.takes_value(true))
+		)
+		.subcommand(SubCommand::with_name("progress")
+			.about("shows how your grades have gone over time, flagging ones below your running average")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSE")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id; defaults to the one in a .handins file in or above this directory")
+				.index(1))
+		)
+		.subcommand(SubCommand::with_name("next")
+			.about("shows the nearest upcoming ungraded deadline across your courses")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSES")
+				.help("courses to check (defaults to every course handins knows about)")
+				.multiple(true)
+				.index(1))
+		)
+		.subcommand(SubCommand::with_name("digest")
+			.about("prints a line per course with your grade, ungraded count and next deadline, for cron and the like")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSES")
+				.help("courses to summarize (defaults to every course handins knows about)")
+				.multiple(true)
+				.index(1))
+		)
+		.subcommand(SubCommand::with_name("export")
+			.about("exports all of your data for a given course to a json file")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSE")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id; defaults to the one in a .handins file in or above this directory")
+				.index(1))
+			.arg(Arg::with_name("out")
+				.short("o")
+				.long("out")
+				.help("file to write the export to (defaults to <COURSE>.json)")
+				.takes_value(true))
 		)
 		.subcommand(SubCommand::with_name("submit")
 			.about("submits your file to the class/assignment specified: not implemented yet")
@@ -64,15 +515,17 @@ cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n"
 			.arg(Arg::with_name("FILE")
 				.help("path to the file to submit")
 				.required_unless("file")
+				.required_unless("git-archive")
 				.index(1))
 			.arg(Arg::with_name("COURSE")
-				.help("class to submit your file to")
+				.help("class to submit your file to, or its numeric handins id")
 				.required_unless("course")
 				.index(2))
 			.arg(Arg::with_name("ASSIGNMENT")
 				.help("name of the assignment to submit to")
 				.required_unless("name")
 				.required_unless("recent")
+				.required_unless("assignment-file")
 				.index(3))
 			.arg(Arg::with_name("file")
 				.short("i")
@@ -82,24 +535,39 @@ cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n"
 			.arg(Arg::with_name("course")
 				.short("c")
 				.long("course")
-				.help("class to submit your file to")
+				.help("class to submit your file to, or its numeric handins id")
 				.takes_value(true))
 			.arg(Arg::with_name("name")
 				.short("a")
 				.long("assignment")
 				.help("name of the assignment to submit to")
 				.takes_value(true))
+			.arg(Arg::with_name("assignment-file")
+				.long("assignment-file")
+				.value_name("PATH")
+				.help("read the name of the assignment to submit to from a file")
+				.takes_value(true))
 			.arg(Arg::with_name("hours")
 				.short("H")
 				.long("hours")
-				.help("number of hours you worked on the homework submitted")
-				.required(true)
+				.help("number of hours you worked on the homework submitted; required unless your config has a default for the course")
 				.takes_value(true))
 			.arg(Arg::with_name("notes")
 				.short("n")
 				.long("notes")
 				.help("any additional student notes you want to leave for handins")
 				.takes_value(true))
+			.arg(Arg::with_name("text")
+				.long("text")
+				.value_name("ANSWER")
+				.help("a text answer to send along with the file, for assignments that take both")
+				.takes_value(true))
+			.arg(Arg::with_name("text-file")
+				.long("text-file")
+				.value_name("PATH")
+				.help("like --text, but read the answer from PATH")
+				.conflicts_with("text")
+				.takes_value(true))
 			.arg(Arg::with_name("wait")
 				.short("w")
 				.long("wait")
@@ -107,168 +575,1213 @@ cs2510a  	--		Fundamentals of Computer Science 2 Accelerated\n"
 			.arg(Arg::with_name("recent")
 				.short("r")
 				.long("recent")
-				.help("choose the most recently assigned homework to submit to")))
-	.get_matches();
-
-    // using to debug cookie values, if necessary 
-    let client = handins_login::<Jar>(None).await?;
+				.help("choose the most recently assigned homework to submit to"))
+			.arg(Arg::with_name("git-archive")
+				.long("git-archive")
+				.help("submit a zip of the current git HEAD instead of a file, noting the commit (use with -c and -a)")
+				.conflicts_with("file"))
+			.arg(Arg::with_name("expect-ext")
+				.long("expect-ext")
+				.value_name("EXT")
+				.help("warn if the file doesn't end in .EXT (an error with --strict); overrides [extensions] in your config")
+				.takes_value(true))
+			.arg(Arg::with_name("include-graded")
+				.long("include-graded")
+				.help("also consider assignments that already have a grade, for courses that allow resubmission"))
+			.arg(Arg::with_name("confirm-grade")
+				.long("confirm-grade")
+				.help("before resubmitting to a graded assignment, even a resubmittable one, show its grade and make you type `replace`"))
+			.arg(Arg::with_name("force")
+				.long("force")
+				.help("resubmit to graded assignments without asking about their current grade")))
+		.subcommand(SubCommand::with_name("plan")
+			.about("calculates a grade from a csv of assignments, without logging in")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("from-csv")
+				.long("from-csv")
+				.value_name("FILE")
+				.help("csv with `name,grade,weight` rows; leave the grade blank for ungraded assignments")
+				.required(true)
+				.takes_value(true))
+			.arg(Arg::with_name("format")
+				.short("f")
+				.long("format")
+				.help("how to print the results")
+				.possible_values(&format::FORMATS)
+				.takes_value(true)))
+		.subcommand(SubCommand::with_name("check-local")
+			.about("runs a course's local test command (from your config) on a file, before you spend a submission on it")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.arg(Arg::with_name("COURSE")
+				.help("name of the course taken (cs2510, cs2510a), or its numeric handins id")
+				.required(true)
+				.index(1))
+			.arg(Arg::with_name("FILE")
+				.help("the solution to check")
+				.required(true)
+				.index(2)))
+		.subcommand(SubCommand::with_name(clipboard::SERVE)
+			.about("holds the clipboard for --clipboard after the command that set it exits")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.setting(AppSettings::Hidden))
+		.subcommand(SubCommand::with_name("selftest")
+			.about("checks that the scrapers still read the pages bundled with this build")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com")
+			.setting(AppSettings::Hidden))
+		.subcommand(SubCommand::with_name("init")
+			.about("asks for your username, a default course and output format, checks that you can log in, and writes a config file")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com"))
+		.subcommand(SubCommand::with_name("profiles")
+			.about("lists the accounts in your config file that --profile can pick from")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com"))
+		.subcommand(SubCommand::with_name("repl")
+			.about("logs in once, then reads and runs subcommands until you exit")
+			.version("0.1")
+			.author("Sam Lyon <sam.c.lyon@gmail.com"))
+}
 
+async fn run_subcommand(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
     match matches.subcommand() {
-        ("grade", Some(sub_matches)) => fetch_grades(&client, sub_matches).await,
-        ("submit", Some(sub_matches)) => submit_file(&client, sub_matches).await,
-        ("ungraded", Some(sub_matches)) => fetch_ungraded(&client, sub_matches).await,
+        ("grade", Some(sub_matches)) => fetch_grades(client, sub_matches).await,
+        ("submit", Some(sub_matches)) => submit_file(client, sub_matches).await,
+        ("ungraded", Some(sub_matches)) => fetch_ungraded(client, sub_matches).await,
+        ("export", Some(sub_matches)) => export_course(client, sub_matches).await,
+        ("next", Some(sub_matches)) => next_deadline(client, sub_matches).await,
+        ("digest", Some(sub_matches)) => digest(client, sub_matches).await,
+        ("assignments", Some(sub_matches)) => list_assignments(client, sub_matches).await,
+        ("plan", Some(sub_matches)) => plan_grades(sub_matches).await,
+        ("selftest", Some(_)) => selftest::run(),
+        ("check-local", Some(sub_matches)) => check_local(&Config::load()?, sub_matches).await,
+        ("profiles", Some(_)) => {
+            list_profiles(&Config::load()?);
+            Ok(())
+        }
+        ("search", Some(sub_matches)) => search_assignments(client, sub_matches).await,
+        ("progress", Some(sub_matches)) => show_progress(client, sub_matches).await,
         _ => Err("must use a supported subcommand with the handins app!")?,
     }
 }
 
-async fn fetch_grades(
-    client: &Client,
+// the subcommands that find their course with `course_arg`
+const COURSE_ARG_SUBCOMMANDS: [&str; 6] = ["grade", "ungraded", "assignments", "progress", "search", "export"];
+
+// COURSE if it was given, otherwise whatever a .handins file in this
+// directory or above it says, otherwise the config file's `course`
+fn course_arg(matches: &ArgMatches<'_>) -> Result<String, Box<dyn Error>> {
+    if let Some(course) = matches.value_of("COURSE").map(str::to_owned).or_else(project::marker_course) {
+        return Ok(course);
+    }
+    Config::load()?.course.ok_or_else(|| {
+        format!(
+            "you must input a course, or run this inside a project with a {} file naming one! \
+             supported courses: cs2510, cs2510a",
+            project::MARKER
+        )
+        .into()
+    })
+}
+
+// --format if it was given, then the course's format from the config file,
+// then the config file's global `format`, then plain
+fn format_arg(matches: &ArgMatches<'_>, course: Option<&str>) -> Result<Format, Box<dyn Error>> {
+    match matches.value_of("format") {
+        Some(format) => Ok(format.parse()?),
+        None => Ok(Config::load()?.format(course).unwrap_or("plain").parse()?),
+    }
+}
+
+// asks for the basics, checks that the account can actually log in, and
+// writes a starter config file with the answers
+async fn init(
+    instance: Box<dyn Instance>,
+    password_fd: Option<i32>,
+    options: &ClientOptions,
+) -> Result<(), Box<dyn Error>> {
+    let path = paths::config_file().ok_or("couldn't find a config directory for this platform")?;
+    if path.exists() {
+        return Err(format!("{} already exists; edit it by hand instead", path.display()))?;
+    }
+
+    print!("handins username: ");
+    io::stdout().flush().unwrap();
+    let username = read_answer()?.trim().to_owned();
+    if username.is_empty() {
+        return Err("no username provided!")?;
+    }
+
+    // the password is only used for this test login, and never saved
+    let known_courses = instance.known_courses().join(", ");
+    let client = handins_login::<Jar>(None, instance, Some(&username), password_fd, true, options).await?;
+    println!("logged in as {}", username);
+
+    let mut course = None;
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        print!("default course, e.g. {} (blank for none): ", known_courses);
+        io::stdout().flush().unwrap();
+        let answer = read_answer()?.trim().to_owned();
+        if answer.is_empty() || resolve_course(client.instance(), &answer).is_ok() {
+            course = Some(answer).filter(|answer| !answer.is_empty());
+            break;
+        }
+        println!("{} isn't a course handins knows, trying again...", answer);
+    }
+
+    let mut format = None;
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        print!("output format, one of {} (blank for plain): ", format::FORMATS.join(", "));
+        io::stdout().flush().unwrap();
+        let answer = read_answer()?.trim().to_lowercase();
+        if answer.is_empty() || answer.parse::<Format>().is_ok() {
+            format = Some(answer).filter(|answer| !answer.is_empty());
+            break;
+        }
+        println!("couldn't get response, trying again...");
+    }
+
+    let contents = toml::to_string(&InitConfig {
+        username,
+        course,
+        format,
+    })?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&path, format!("# written by `handins init`\n{}", contents))?;
+    println!("wrote {}", path.display());
+
+    Ok(())
+}
+
+// the settings `init` asks about, as they appear in the config file
+#[derive(Serialize)]
+struct InitConfig {
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    course: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+}
+
+// runs the configured check for a course and says whether it passed,
+// exiting with 1 if it didn't so scripts can stop before submitting
+async fn check_local(config: &Config, matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let course = matches
+        .value_of("COURSE")
+        .ok_or("you must input a course! use --help to see supported courses")?;
+    let file = matches
+        .value_of("FILE")
+        .ok_or("you must input a file to check!")?;
+
+    let check = config.check(course).ok_or_else(|| {
+        format!("no local check for {}; add one to your config under [checks.{}]", course, course)
+    })?;
+
+    // substituted per argument rather than handed to a shell, so file names
+    // with spaces or quotes in them can't change the command
+    let args: Vec<String> = split_args(&check.command)
+        .into_iter()
+        .map(|arg| arg.replace("{file}", file))
+        .collect();
+    let (program, args) = args
+        .split_first()
+        .ok_or_else(|| format!("the check command for {} is empty", course))?;
+
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| format!("couldn't run {}: {}", program, e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    print!("{}", stdout);
+    eprint!("{}", stderr);
+
+    let found = check
+        .pass_pattern
+        .as_ref()
+        .is_none_or(|pattern| stdout.contains(pattern.as_str()) || stderr.contains(pattern.as_str()));
+
+    if output.status.success() && found {
+        println!("check passed for {}", file);
+        Ok(())
+    } else {
+        println!("check failed for {}", file);
+        Err(terminal::Exit(1).into())
+    }
+}
+
+fn list_profiles(config: &Config) {
+    if config.profiles.is_empty() {
+        println!("no profiles yet; add some to your config under [profiles.<name>]");
+        return;
+    }
+
+    let mut profiles: Vec<_> = config.profiles.iter().collect();
+    profiles.sort_by(|a, b| a.0.cmp(b.0));
+
+    let width = profiles.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, profile) in profiles {
+        println!("{:<width$}  {}", name, profile.username, width = width);
+    }
+}
+
+// runs subcommands against the same logged-in client until the user quits,
+// so a session of checking grades and submitting only logs in once
+async fn repl(client: &Handins) -> Result<(), Box<dyn Error>> {
+    println!("logged in! type a subcommand (e.g. `grade cs2510`), `help`, or `exit`");
+
+    loop {
+        print!("handins> ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line)? == 0 {
+            println!();
+            return Ok(());
+        }
+
+        let words = split_args(&line);
+        match words.first().map(|word| word.as_str()) {
+            None => continue,
+            Some("exit") | Some("quit") => return Ok(()),
+            Some("repl") => {
+                println!("already in the repl!");
+                continue;
+            }
+            Some(_) => {}
+        }
+
+        // clap errors (including --help) are just printed, so a typo doesn't
+        // end the session
+        let matches = match cli().get_matches_from_safe(std::iter::once("handins".to_owned()).chain(words)) {
+            Ok(matches) => matches,
+            Err(e) => {
+                println!("{}", e.message);
+                continue;
+            }
+        };
+
+        if let Err(e) = run_subcommand(client, &matches).await {
+            println!("error: {}", e);
+        }
+    }
+}
+
+// splits a line of input into arguments like a (very) simple shell would:
+// on whitespace, except inside single or double quotes
+fn split_args(line: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+// `grade` flags that print extra text after the grades
+const TEXT_ONLY_GRADE_FLAGS: [&str; 5] = ["contributions", "explain", "by-category", "whatif", "diff"];
+
+async fn fetch_grades(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let course = course_arg(matches)?;
+    let course = course.as_str();
+
+    let course_id = resolve_course(client.instance(), course)
+        .map_err(|_| "not a supported course for handins at this time")?;
+
+    let template: Option<Template> = matches.value_of("template").map(str::parse).transpose()?;
+
+    // these only have a text form, which would end up in the middle of the
+    // json, so say so before fetching the grades
+    let format: Format = format_arg(matches, Some(course))?;
+    if format == Format::Json {
+        for flag in TEXT_ONLY_GRADE_FLAGS {
+            if matches.is_present(flag) {
+                return Err(format!("--{} can't be combined with --format json", flag))?;
+            }
+        }
+    }
+
+    let assignments = assignments(client, course_id).await?;
+
+    // just the one assignment, for checking whether something's been graded
+    if let Some(query) = matches.value_of("assignment") {
+        let best = fuzzy_match(&assignments, query)
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("no assignments matched `{}`", query))?;
+        let assignment = &assignments[best];
+        match (assignment.grade, assignment.points) {
+            (Some(grade), Some(points)) => println!(
+                "{}: {:.2} ({} / {})",
+                assignment.name, grade, points.earned, points.possible
+            ),
+            (Some(grade), None) => println!("{}: {:.2}", assignment.name, grade),
+            (None, _) => println!("{}: ungraded", assignment.name),
+        }
+        return Ok(());
+    }
+
+    let tolerance = match matches.value_of("weight-tolerance") {
+        Some(tolerance) => tolerance
+            .parse::<f64>()
+            .map_err(|_| "weight tolerance must be a number!")?,
+        None => Config::load()?.weight_tolerance(course).unwrap_or(5.0),
+    };
+
+    // a total far from 100 usually means the scraper dropped or doubled an
+    // assignment, or the course weights things unusually
+    let total_weight: f64 = assignments.iter().map(|a| a.weight).sum();
+    if (total_weight - 100.0).abs() > tolerance {
+        eprintln!(
+            "warning: assignment weights total {:.2}%, not 100%; your grades may be off",
+            total_weight
+        );
+    }
+
+    let summary_fields = match matches.values_of("summary-fields") {
+        Some(names) => SummaryFields::from_names(names),
+        None => SummaryFields::all(),
+    };
+
+    if let Some(template) = template {
+        for assignment in &assignments {
+            println!("{}", template.render(assignment));
+        }
+        return Ok(());
+    }
+
+    let drop_lowest = matches
+        .value_of("drop-lowest")
+        .unwrap_or("0")
+        .parse::<usize>()
+        .map_err(|_| "the number of grades to drop must be a whole number!")?;
+
+    let dropped = grade::lowest_graded(&assignments, drop_lowest);
+    if dropped.len() < drop_lowest {
+        eprintln!(
+            "warning: can only drop {} of your {} graded assignments",
+            dropped.len(),
+            assignments.iter().filter(|a| a.graded()).count()
+        );
+    }
+    let summary = grade::calculate_grade(&assignments, drop_lowest);
+    let (cur_grade, min_grade, max_grade, points_left) = summary;
+
+    // a bare number and nothing else
+    if let Some(metric) = matches.value_of("metric") {
+        let precision = matches
+            .value_of("precision")
+            .unwrap_or("2")
+            .parse::<usize>()
+            .map_err(|_| "precision must be a whole number of digits!")?;
+        let value = match metric {
+            "current" => cur_grade,
+            "min" => min_grade,
+            "max" => max_grade,
+            _ => points_left,
+        };
+        println!("{:.*}", precision, value);
+        return Ok(());
+    }
+
+    // for monitoring scripts: just one line, and the answer in the exit code.
+    // this comes before the "dropping" lines so that it stays one line
+    if let Some(threshold) = matches.value_of("passing-threshold") {
+        let threshold = threshold
+            .parse::<f64>()
+            .map_err(|_| "passing threshold must be a number!")?;
+
+        if cur_grade >= threshold {
+            println!("passing: {:.2} (threshold {:.2})", cur_grade, threshold);
+            return Ok(());
+        } else {
+            println!("not passing: {:.2} (threshold {:.2})", cur_grade, threshold);
+            return Err(terminal::Exit(1).into());
+        }
+    }
+
+    // json lists these in the payload instead
+    if format != Format::Json {
+        for assignment in &dropped {
+            println!("dropping {} ({:.2})", assignment.name, assignment.grade.unwrap_or(0.0));
+        }
+    }
+
+    if matches.is_present("sections") {
+        format::print_sections(format, &assignments, &dropped, summary, summary_fields);
+    } else {
+        format::print_grades(format, &assignments, &dropped, summary, summary_fields);
+    }
+
+    // each graded assignment's share of the current grade; these add up to it
+    if matches.is_present("contributions") {
+        let counted: Vec<&Assignment> = assignments
+            .iter()
+            .filter(|a| a.graded() && !dropped.iter().any(|d| d.id == a.id))
+            .collect();
+        let total_weight: f64 = counted.iter().map(|a| a.weight).sum();
+
+        println!();
+        for assignment in counted {
+            println!(
+                "{} contributes {:.1}% to your grade",
+                assignment.name,
+                assignment.grade.unwrap_or(0.0) * assignment.weight / total_weight
+            );
+        }
+    }
+
+    if matches.is_present("explain") {
+        print_grade_explanation(&assignments, &dropped, summary);
+    }
+
+    if matches.is_present("by-category") {
+        let config = Config::load()?;
+        let categories = config.categories(course).ok_or_else(|| {
+            format!("no grading categories for {} in your config file", course)
+        })?;
+        print_category_breakdown(&assignments, categories);
+    }
+
+    if let Some(whatif) = matches.value_of("whatif") {
+        let mut projected = assignments.clone();
+        println!();
+        println!("What if:");
+        for (idx, grade) in parse_whatif(&assignments, whatif)? {
+            println!("  {} gets {:.2}", projected[idx].name, grade);
+            projected[idx].grade = Some(grade);
+        }
+
+        let (new_grade, new_min, new_max, _) = grade::calculate_grade(&projected, drop_lowest);
+        let (_, min_grade, max_grade, _) = summary;
+        println!("  current grade: {:.2} -> {:.2}", cur_grade, new_grade);
+        println!("  minimum grade: {:.2} -> {:.2}", min_grade, new_min);
+        println!("  maximum grade: {:.2} -> {:.2}", max_grade, new_max);
+    }
+
+    if let Some(path) = matches.value_of("diff") {
+        let old: Export = serde_json::from_str(&tokio::fs::read_to_string(path).await?)?;
+        if old.schema_version != export::SCHEMA_VERSION {
+            return Err(format!(
+                "{} was exported with schema version {}, but only version {} is supported",
+                path,
+                old.schema_version,
+                export::SCHEMA_VERSION
+            ))?;
+        }
+        if old.course_id != course_id {
+            eprintln!(
+                "warning: {} is a snapshot of {}, not {}",
+                path, old.course, course
+            );
+        }
+
+        print_grade_diff(&old, &assignments, cur_grade, drop_lowest);
+    }
+
+    Ok(())
+}
+
+// walks through the same arithmetic as `calculate_grade`, so the numbers
+// above can be checked by hand
+fn print_grade_explanation(assignments: &[Assignment], dropped: &[&Assignment], summary: grade::GradeSummary) {
+    let (cur_grade, min_grade, max_grade, max_points) = summary;
+    let is_dropped = |a: &Assignment| dropped.iter().any(|d| d.id == a.id);
+
+    let counted: Vec<&Assignment> = assignments.iter().filter(|a| !is_dropped(a)).collect();
+    let graded: Vec<(&Assignment, f64)> = counted
+        .iter()
+        .filter_map(|a| a.grade.map(|grade| (*a, grade)))
+        .collect();
+
+    let scaled: f64 = graded.iter().map(|(a, grade)| grade * a.weight).sum();
+    let graded_weight: f64 = graded.iter().map(|(a, _)| a.weight).sum();
+    let future_weight: f64 = counted.iter().filter(|a| !a.graded()).map(|a| a.weight).sum();
+    let dropped_weight: f64 = dropped.iter().map(|a| a.weight).sum();
+    let course_weight = 100.0 - dropped_weight;
+
+    println!();
+    println!("graded assignments, as grade x weight:");
+    let width = graded.iter().map(|(a, _)| a.name.len()).max().unwrap_or(0);
+    for (assignment, grade) in &graded {
+        println!(
+            "  {:<width$}  {:>6.2} x {:>5.2} = {:>8.2}",
+            assignment.name,
+            grade,
+            assignment.weight,
+            grade * assignment.weight,
+            width = width
+        );
+    }
+    for assignment in dropped {
+        println!("  {} is dropped, taking its {:.2} weight out of the course", assignment.name, assignment.weight);
+    }
+
+    println!();
+    println!("sum of grade x weight (S):        {:.2}", scaled);
+    println!("weight graded so far (W):         {:.2}", graded_weight);
+    println!("weight not yet graded (F):        {:.2}", future_weight);
+    println!("course weight, 100 - dropped (C): {:.2}", course_weight);
+
+    println!();
+    println!("current grade = S / W = {:.2} / {:.2} = {:.2}%", scaled, graded_weight, cur_grade);
+    println!(
+        "minimum grade = S / C = {:.2} / {:.2} = {:.2}%  (everything ungraded scores 0)",
+        scaled, course_weight, min_grade
+    );
+    println!(
+        "maximum grade = (S + 100 x (C - W)) / C = {:.2}%  (everything ungraded scores 100)",
+        max_grade
+    );
+    println!(
+        "points left   = (S + 100 x F) / (W + F) - S / W = {:.2}",
+        max_points
+    );
+}
+
+fn print_category_breakdown(assignments: &[Assignment], categories: &HashMap<String, Category>) {
+    let (grades, uncategorized) = grade::category_grades(assignments, categories);
+    let width = grades.iter().map(|g| g.name.len()).max().unwrap_or(0).max("Category".len());
+
+    println!();
+    println!(
+        "{:<width$} {:>8} {:>8} {:>8}",
+        "Category",
+        "Weight",
+        "Average",
+        "Graded",
+        width = width + 5
+    );
+    for category in &grades {
+        let average = match category.average {
+            Some(average) => format!("{:.2}", average),
+            None => "-".to_owned(),
+        };
+        println!(
+            "{:<width$} {:>8.2} {:>8} {:>8}",
+            category.name,
+            category.weight,
+            average,
+            format!("{}/{}", category.graded, category.total),
+            width = width + 5
+        );
+    }
+
+    match grade::category_weighted_grade(&grades) {
+        Some(grade) => println!("Your category-weighted grade: {:.2}", grade),
+        None => println!("Nothing in any category has been graded yet"),
+    }
+
+    if !uncategorized.is_empty() {
+        eprintln!("warning: these assignments aren't in any category, so they weren't counted:");
+        for assignment in uncategorized {
+            eprintln!("  {}", assignment.name);
+        }
+    }
+}
+
+// prints everything that moved between an old export and the current grades
+fn print_grade_diff(old: &Export, assignments: &[Assignment], cur_grade: f64, drop_lowest: usize) {
+    println!();
+    println!("Changes since {}:", old.exported_at.format("%Y-%m-%d %H:%M"));
+
+    let mut changed = false;
+    for assignment in assignments {
+        let previous = old.assignments.iter().find(|a| a.id == assignment.id);
+
+        match (previous.and_then(|a| a.grade), assignment.grade) {
+            (None, Some(grade)) => {
+                println!("  newly graded: {} ({:.2})", assignment.name, grade);
+                changed = true;
+            }
+            (Some(before), Some(after)) if (before - after).abs() > f64::EPSILON => {
+                println!(
+                    "  regraded: {} ({:.2} -> {:.2})",
+                    assignment.name, before, after
+                );
+                changed = true;
+            }
+            (Some(before), None) => {
+                println!("  grade removed: {} (was {:.2})", assignment.name, before);
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+
+    if !changed {
+        println!("  no grades changed");
+    }
+
+    let (old_grade, _, _, _) = grade::calculate_grade(&old.assignments, drop_lowest);
+    println!(
+        "  current grade: {:.2} -> {:.2} ({:+.2})",
+        old_grade,
+        cur_grade,
+        cur_grade - old_grade
+    );
+}
+
+async fn fetch_ungraded(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let course = course_arg(matches)?;
+    let course = course.as_str();
+
+    let course_id = resolve_course(client.instance(), course)
+        .map_err(|_| "not a supported course for handins at this time")?;
+
+    let assignments: Vec<Assignment> = assignments(client, course_id).await?;
+    let ungraded_assignments: Vec<&Assignment> =
+        assignments.iter().filter(|a| a.grade.is_none()).collect();
+    let format: Format = format_arg(matches, Some(course))?;
+
+    if matches.is_present("count") {
+        let ungraded = ungraded_assignments.len();
+        match format {
+            Format::Json => output::print_json("ungraded-count", output::UngradedCount { ungraded }),
+            _ => println!("{}", ungraded),
+        }
+        return Ok(());
+    }
+
+    if ungraded_assignments.is_empty() && format != Format::Json {
+        println!("no ungraded assignments — you're all caught up!");
+        return Ok(());
+    }
+
+    format::print_ungraded(format, &ungraded_assignments);
+
+    Ok(())
+}
+
+async fn list_assignments(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let course = course_arg(matches)?;
+    let course = course.as_str();
+
+    let course_id = resolve_course(client.instance(), course)
+        .map_err(|_| "not a supported course for handins at this time")?;
+
+    let assignments: Vec<Assignment> = assignments(client, course_id).await?;
+    let listed: Vec<&Assignment> = assignments
+        .iter()
+        .filter(|a| !matches.is_present("only-graded") || a.graded())
+        .filter(|a| !matches.is_present("only-ungraded") || !a.graded())
+        .collect();
+
+    let format: Format = format_arg(matches, Some(course))?;
+    if listed.is_empty() && format != Format::Json {
+        println!("no matching assignments for this course");
+        return Ok(());
+    }
+
+    format::print_assignments(format, &listed);
+
+    Ok(())
+}
+
+// each graded assignment in the order it was due, next to the average of
+// everything graded before it, so a slump stands out
+async fn show_progress(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let course = course_arg(matches)?;
+    let course = course.as_str();
+
+    let course_id = resolve_course(client.instance(), course)
+        .map_err(|_| "not a supported course for handins at this time")?;
+
+    let assignments: Vec<Assignment> = assignments(client, course_id).await?;
+    let mut graded: Vec<(&Assignment, f64)> = assignments
+        .iter()
+        .filter_map(|a| a.grade.map(|grade| (a, grade)))
+        .collect();
+    // anything without a deadline goes at the end
+    graded.sort_by_key(|(a, _)| (a.due_date.is_none(), a.due_date));
+
+    if graded.is_empty() {
+        println!("nothing has been graded in this course yet");
+        return Ok(());
+    }
+
+    let width = graded.iter().map(|(a, _)| a.name.len()).max().unwrap_or(0);
+    let mut total = 0.0;
+    for (i, (assignment, grade)) in graded.iter().enumerate() {
+        let flag = if i > 0 && *grade < total / i as f64 {
+            format!("  below your average of {:.2}", total / i as f64)
+        } else {
+            String::new()
+        };
+        println!("{:<width$}  {:>6.2}{}", assignment.name, grade, flag, width = width);
+        total += grade;
+    }
+
+    // the last few against everything, for which way things are heading
+    let recent: Vec<f64> = graded.iter().rev().take(3).map(|(_, grade)| *grade).collect();
+    let recent_avg = recent.iter().sum::<f64>() / recent.len() as f64;
+    let overall_avg = total / graded.len() as f64;
+    println!();
+    println!(
+        "your last {} averaged {:.2}, against {:.2} overall",
+        recent.len(),
+        recent_avg,
+        overall_avg
+    );
+
+    Ok(())
+}
+
+async fn search_assignments(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let course = course_arg(matches)?;
+    let course = course.as_str();
+
+    let course_id = resolve_course(client.instance(), course)
+        .map_err(|_| "not a supported course for handins at this time")?;
+
+    let query = matches
+        .value_of("QUERY")
+        .ok_or("you must input something to search for!")?;
+
+    let limit = matches
+        .value_of("limit")
+        .unwrap_or("5")
+        .parse::<usize>()
+        .map_err(|_| "limit must be a whole number!")?;
+
+    let assignments: Vec<Assignment> = assignments(client, course_id).await?;
+    let found = fuzzy_match(&assignments, query);
+
+    if found.is_empty() {
+        println!("no assignments matched `{}`", query);
+        return Ok(());
+    }
+
+    let width = found
+        .iter()
+        .take(limit)
+        .map(|&i| assignments[i].name.len())
+        .max()
+        .unwrap_or(0);
+
+    println!(
+        "{:<6} {:<width$} {:>8}   Due",
+        "Score",
+        "Assignment",
+        "Grade",
+        width = width + 5
+    );
+
+    for &i in found.iter().take(limit) {
+        let assignment = &assignments[i];
+        let grade = match assignment.grade {
+            Some(grade) => format!("{:.2}", grade),
+            None => "-".to_owned(),
+        };
+
+        println!(
+            "{:<6.2} {:<width$} {:>8}   {}",
+            match_score(&assignment.name, query),
+            assignment.name,
+            grade,
+            format::fmt_due_date(assignment),
+            width = width + 5
+        );
+    }
+
+    Ok(())
+}
+
+async fn plan_grades(matches: &ArgMatches<'_>) -> Result<(), Box<dyn Error>> {
+    let path = matches
+        .value_of("from-csv")
+        .ok_or("you must input a csv file to plan from!")?;
+
+    let csv = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("couldn't read {}: {}", path, e))?;
+    let assignments = parse_plan_csv(&csv)?;
+
+    if assignments.is_empty() {
+        return Err(format!("{} doesn't have any assignments in it", path))?;
+    }
+
+    let format: Format = format_arg(matches, None)?;
+    format::print_grades(
+        format,
+        &assignments,
+        &[],
+        grade::calculate_grade(&assignments, 0),
+        SummaryFields::all(),
+    );
+
+    Ok(())
+}
+
+// reverse date order (most recent first), with anything that has no due
+// date last
+fn sort_newest_first(assignments: &mut [Assignment]) {
+    assignments.sort_by_key(|a| std::cmp::Reverse(a.due_date));
+}
+
+// reads `name,grade,weight` rows, splitting from the right so that names
+// can still contain commas. a header row is allowed, and skipped
+fn parse_plan_csv(csv: &str) -> Result<Vec<Assignment>, Box<dyn Error>> {
+    let mut assignments = vec![];
+
+    for (i, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let mut fields = line.rsplitn(3, ',');
+        let weight = fields.next().unwrap_or("").trim();
+        let grade = fields.next().map(|grade| grade.trim());
+        let name = fields.next().map(|name| name.trim().trim_matches('"'));
+
+        let (name, grade) = match (name, grade) {
+            (Some(name), Some(grade)) => (name, grade),
+            _ => return Err(format!("line {}: expected `name,grade,weight`", i + 1))?,
+        };
+
+        if i == 0 && weight.parse::<f64>().is_err() {
+            continue;
+        }
+
+        let weight = weight
+            .parse::<f64>()
+            .map_err(|_| format!("line {}: weight `{}` isn't a number", i + 1, weight))?;
+        let grade = match grade {
+            "" => None,
+            grade => Some(
+                grade
+                    .parse::<f64>()
+                    .map_err(|_| format!("line {}: grade `{}` isn't a number", i + 1, grade))?,
+            ),
+        };
+
+        // a plan has no deadlines, so nothing in it is ever late
+        assignments.push(Assignment::new(name.to_owned(), i as i64, grade, weight, None));
+    }
+
+    Ok(assignments)
+}
+
+async fn next_deadline(
+    client: &Handins,
+    matches: &ArgMatches<'_>,
+) -> Result<(), Box<dyn Error>> {
+    let explicit = matches.values_of("COURSES").is_some();
+    let courses: Vec<&str> = match matches.values_of("COURSES") {
+        Some(courses) => courses.collect(),
+        None => client.instance().known_courses(),
+    };
+
+    let mut next: Option<(&str, Assignment, DateTime<FixedOffset>)> = None;
+    for course in courses {
+        let course_id = resolve_course(client.instance(), course)
+            .map_err(|_| format!("{} is not a supported course for handins at this time", course))?;
+
+        // when checking every known course, the student won't be enrolled in
+        // most of them, so just skip the ones we can't read
+        let assignments = match assignments(client, course_id).await {
+            Ok(assignments) => assignments,
+            Err(e) if explicit => return Err(e),
+            Err(_) => continue,
+        };
+
+        let upcoming = assignments
+            .into_iter()
+            .filter(|a| !a.graded() && !a.late())
+            .filter_map(|a| a.due_date.map(|due_date| (a, due_date)))
+            .min_by_key(|(_, due_date)| *due_date);
+
+        if let Some((assignment, due_date)) = upcoming {
+            match &next {
+                Some((_, _, soonest)) if *soonest <= due_date => {}
+                _ => next = Some((course, assignment, due_date)),
+            }
+        }
+    }
+
+    match next {
+        Some((course, assignment, due_date)) if format::plain() => println!(
+            "{} ({}) is due {}",
+            assignment.name,
+            course,
+            clock::local(due_date).format("%Y-%m-%d %H:%M")
+        ),
+        Some((course, assignment, due_date)) => println!(
+            "{} ({}) is due {}, in {}",
+            assignment.name,
+            course,
+            clock::local(due_date).format("%a %b %e %l:%M %p"),
+            assignment.time_left().map(format_duration).unwrap_or_default()
+        ),
+        None => println!("nothing upcoming, you're all caught up!"),
+    }
+
+    Ok(())
+}
+
+// one line per course, meant for cron or a login message, so it never asks
+// anything and always prints the same shape of line
+async fn digest(
+    client: &Handins,
     matches: &ArgMatches<'_>,
 ) -> Result<(), Box<dyn Error>> {
-    let course: &str = matches
-        .value_of("COURSE")
-        .ok_or("you must input a course! supported courses: cs2510, cs2510a")?;
-
-    let course_id = lookup_course(course)
-        .map_err(|_| "not a supported course for handins at this time")?;
+    let explicit = matches.values_of("COURSES").is_some();
+    let courses: Vec<&str> = match matches.values_of("COURSES") {
+        Some(courses) => courses.collect(),
+        None => client.instance().known_courses(),
+    };
 
-    let assignments = assignments(client, course_id).await?;
+    for course in courses {
+        let course_id = resolve_course(client.instance(), course)
+            .map_err(|_| format!("{} is not a supported course for handins at this time", course))?;
 
-    let width = assignments.iter().map(|s| s.name.len()).max().unwrap();
+        // same as `next`: unenrolled courses are only an error if asked for
+        let assignments = match assignments(client, course_id).await {
+            Ok(assignments) => assignments,
+            Err(e) if explicit => return Err(e),
+            Err(_) => continue,
+        };
 
-    let (cur_grade, min_grade, max_grade, max_points) = calculate_grade(&assignments);
+        let grade = if assignments.iter().any(|a| a.graded()) {
+            let (cur_grade, _, _, _) = grade::calculate_grade(&assignments, 0);
+            format!("{:.2}%", cur_grade)
+        } else {
+            "-".to_owned()
+        };
+        let ungraded = assignments.iter().filter(|a| !a.graded()).count();
+        let next = assignments
+            .iter()
+            .filter(|a| !a.graded() && !a.late())
+            .filter_map(|a| a.due_date.map(|due_date| (a, due_date)))
+            .min_by_key(|(_, due_date)| *due_date);
+
+        let next = match next {
+            Some((assignment, due_date)) if format::plain() => format!(
+                "next \"{}\" due {}",
+                assignment.name,
+                clock::local(due_date).format("%Y-%m-%d %H:%M")
+            ),
+            Some((assignment, due_date)) => format!(
+                "next \"{}\" due {} (in {})",
+                assignment.name,
+                clock::local(due_date).format("%Y-%m-%d %H:%M"),
+                assignment.time_left().map(format_duration).unwrap_or_default()
+            ),
+            None => "nothing due".to_owned(),
+        };
+
+        println!("{}: grade {}, {} ungraded, {}", course, grade, ungraded, next);
+    }
 
-    println!(
-        "{:<width$} {:<8} {:>8}",
-        "Homework",
-        "Grades",
-        "Weight",
-        width = width + 5
-    );
+    Ok(())
+}
 
-    for assignment in assignments {
-        let fmt_name = format!("{}:", assignment.name);
+fn format_duration(duration: chrono::Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
 
-        if let Some(grade) = assignment.grade {
-            println!(
-                "{:<width$} {:<8.2} {:>8.2}",
-                fmt_name,
-                grade,
-                assignment.weight,
-                width = width + 5
-            );
-        }
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
     }
-    println!(
-        "{:<width$} {:<.2}",
-        "Your current grade:",
-        cur_grade,
-        width = width + 5
-    );
-    println!(
-        "{:<width$} {:<.2}",
-        "Your minimum grade:",
-        min_grade,
-        width = width + 5
-    );
-    println!(
-        "{:<width$} {:<.2}",
-        "Your maximum grade:",
-        max_grade,
-        width = width + 5
-    );
-    println!(
-        "{:<width$} {:<.2}",
-        "Ungraded points you can earn:",
-        max_points,
-        width = width + 5
-    );
-
-    Ok(())
 }
 
-async fn fetch_ungraded(
-    client: &Client,
+async fn export_course(
+    client: &Handins,
     matches: &ArgMatches<'_>,
 ) -> Result<(), Box<dyn Error>> {
-    let course: &str = matches
-        .value_of("COURSE")
-        .ok_or("you must input a course! supported courses: cs2510, cs2510a")?;
+    let course = course_arg(matches)?;
+    let course = course.as_str();
 
-    let course_id = lookup_course(course)
+    let course_id = resolve_course(client.instance(), course)
         .map_err(|_| "not a supported course for handins at this time")?;
 
-    let assignments: Vec<Assignment> = assignments(&client, course_id).await?;
-    let ungraded_assignments: Vec<&Assignment> =
-        assignments.iter().filter(|a| a.grade.is_none()).collect();
-    let width = ungraded_assignments
-        .iter()
-        .map(|a| a.name.len())
-        .max()
-        .unwrap();
+    let out = matches
+        .value_of("out")
+        .map(|out| out.to_owned())
+        .unwrap_or_else(|| format!("{}.json", course));
 
-    println!(
-        "{:<width$} {:<8}",
-        "Assignment",
-        "Weight",
-        width = width + 5
+    let assignments = assignments(client, course_id).await?;
+    let export = Export::new(
+        course.to_owned(),
+        course_id,
+        clock::now(),
+        assignments,
     );
 
-    for assignment in ungraded_assignments {
-        println!(
-            "{:<width$} {:<.2}",
-            assignment.name,
-            assignment.weight,
-            width = width + 5
-        );
-    }
+    tokio::fs::write(&out, serde_json::to_string_pretty(&export)?).await?;
+    println!("exported {} assignments to {}", export.assignments.len(), out);
 
     Ok(())
 }
 
-async fn submit_file<'b>(
-    client: &Client,
+async fn submit_file(
+    client: &Handins,
     matches: &ArgMatches<'_>,
 ) -> Result<(), Box<dyn Error>> {
-    let file_name: String = matches
-        .value_of("FILE")
-        .or(matches.value_of("infile"))
-        .ok_or("you must input a homework file to submit!")?
-        .to_owned();
-
-    let mut file = tokio::fs::File::open(&file_name).await?;
-    let mut buffer = vec![];
-    file.read_to_end(&mut buffer).await?;
+    let (file_name, buffer, commit) = if matches.is_present("git-archive") {
+        let (file_name, buffer, commit) = git_archive().await?;
+        (file_name, buffer, Some(commit))
+    } else {
+        let file_name: String = matches
+            .value_of("FILE")
+            .or(matches.value_of("file"))
+            .ok_or("you must input a homework file to submit!")?
+            .to_owned();
+
+        let mut file = tokio::fs::File::open(&file_name).await?;
+        let mut buffer = vec![];
+        file.read_to_end(&mut buffer).await?;
+        (file_name, buffer, None)
+    };
 
     let course = matches
         .value_of("COURSE")
         .or(matches.value_of("course"))
         .ok_or("you must input a course! use --help to see supported courses")?;
 
-    let course_id = lookup_course(course)
+    let course_id = resolve_course(client.instance(), course)
         .map_err(|_| "not a supported course for handins at this time")?;
 
-    let assignment = remove_whitespace(
-        matches
-            .value_of("ASSIGNMENT")
-            .or(matches.value_of("assignment"))
-            .ok_or("you must input an assignment to submit your file to!")?,
-    );
+    let config = Config::load()?;
+    let interactive = !matches.is_present("non-interactive");
+
+    // catches submitting an export or a stray notes file instead of the
+    // actual source. archives are whatever git made them, so aren't checked
+    if commit.is_none() {
+        let expected = matches
+            .value_of("expect-ext")
+            .or_else(|| config.extension(course));
+        if let Some(expected) = expected {
+            let expected = expected.trim_start_matches('.');
+            let matches_ext = Path::new(&file_name)
+                .extension()
+                .is_some_and(|ext| ext.to_string_lossy().eq_ignore_ascii_case(expected));
+            if !matches_ext {
+                let message = format!("{} doesn't end in .{}, which {} expects", file_name, expected, course);
+                if matches.is_present("strict") {
+                    return Err(message.into());
+                }
+                eprintln!("warning: {}", message);
+            }
+        }
+    }
 
-    let hours = matches.value_of("hours")
-        .ok_or("you must input a number of hours you worked on this assignment!")?
-        .parse::<f64>()?;
-    let notes = matches.value_of("notes")
+    // the positional argument wins over -a, which wins over --assignment-file
+    let assignment = match matches.value_of("ASSIGNMENT").or(matches.value_of("name")) {
+        Some(assignment) => Some(assignment.to_owned()),
+        None => match matches.value_of("assignment-file") {
+            Some(path) => Some(read_assignment_file(path).await?),
+            None => None,
+        },
+    };
+    let alias = assignment
+        .as_deref()
+        .and_then(|assignment| config.alias(assignment))
+        .map(|target| target.to_owned());
+    let assignment = match assignment {
+        Some(assignment) => normalize_name(&assignment),
+        None if matches.is_present("recent") => String::new(),
+        None => return Err("you must input an assignment to submit your file to!")?,
+    };
+
+    let hours = match (matches.value_of("hours"), config.hours(course)) {
+        (Some(hours), _) => hours
+            .parse::<f64>()
+            .map_err(|_| "hours must be a number!")?,
+        (None, Some(hours)) => {
+            println!("using the default of {} hours for {} from your config", hours, course);
+            hours
+        }
+        (None, None) => Err("you must input a number of hours you worked on this assignment!")?,
+    };
+    let mut notes = matches.value_of("notes")
         .unwrap_or("").to_owned();
 
+    let text = match (matches.value_of("text"), matches.value_of("text-file")) {
+        (Some(text), _) => Some(text.to_owned()),
+        (None, Some(path)) => Some(
+            tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| format!("couldn't read text answer {}: {}", path, e))?,
+        ),
+        (None, None) => None,
+    };
+
+    // record exactly which commit was submitted
+    if let Some(commit) = commit {
+        if !notes.is_empty() {
+            notes.push('\n');
+        }
+        notes.push_str(&format!("submitted from git commit {}", commit));
+    }
+
     if hours < 0.0 {
         return Err("cannot work on an assignment for negative hours!")?;
     }
 
-    let mut assignments: Vec<Assignment> = assignments(&client, course_id)
+    let mut assignments: Vec<Assignment> = assignments(client, course_id)
         .await?
         .into_iter()
-        .filter(|assignment| !assignment.graded())
+        .filter(|assignment| {
+            matches.is_present("include-graded")
+                || !assignment.graded()
+                || config.resubmittable(&assignment.name)
+        })
         .collect();
 
     // this block of code revolves around getting the correct assignment to submit
@@ -276,17 +1789,26 @@ async fn submit_file<'b>(
     if assignments.is_empty() {
         return Err("all assignments have been graded!")?;
     }
-    // sort by reverse date order (most recent first)
-    assignments.sort_by(|a1, a2| a2.due_date.cmp(&a1.due_date));
+    sort_newest_first(&mut assignments);
+
+    // an alias from the config names its assignment exactly, so there's
+    // nothing to fuzzy match or confirm
+    let aliased = match &alias {
+        Some(target) => Some(
+            assignments
+                .iter()
+                .position(|a| normalize_name(&a.name) == normalize_name(target))
+                .ok_or_else(|| format!("alias for `{}` didn't match any assignments!", target))?,
+        ),
+        None => None,
+    };
 
     let submission_candidate_indices = if matches.is_present("recent") {
         vec![0]
+    } else if let Some(idx) = aliased {
+        vec![idx]
     } else {
-        let mut engine: SimSearch<usize> = SimSearch::new();
-        for (i, item) in assignments.iter().enumerate() {
-            engine.insert(i, &item.name);
-        }
-        engine.search(&assignment)
+        fuzzy_match(&assignments, &assignment)
     };
 
     // at this point, we need to decide how to parse the assignment submitted by the user.
@@ -298,280 +1820,580 @@ async fn submit_file<'b>(
             Err("assignment name didn't match any assignments!")
         } else if matches.is_present("recent") {
             Ok(&assignments[0])
+        } else if aliased.is_some() {
+            Ok(&assignments[submission_candidate_indices[0]])
+        } else if submission_candidate_indices.len() == 1
+            && (!interactive || config.resubmittable(&assignments[submission_candidate_indices[0]].name))
+        {
+            // the one match is the default answer anyways, and practice
+            // assignments aren't worth asking about
+            let assignment = &assignments[submission_candidate_indices[0]];
+            println!("submitting to {}", display_name(&assignments, assignment));
+            Ok(assignment)
+        } else if !interactive {
+            let names: Vec<String> = submission_candidate_indices
+                .iter()
+                .map(|&i| display_name(&assignments, &assignments[i]))
+                .collect();
+            return Err(format!(
+                "several assignments matched ({}), be more specific to submit non-interactively",
+                names.join(", ")
+            ))?;
+        } else if submission_candidate_indices.len() == 1 {
+            match validate_assignment(&assignments, &assignments[submission_candidate_indices[0]]) {
+                Ok(Some(assignment)) => Ok(assignment),
+                Ok(None) => Err("couldn't find the right assignment, shutting down"),
+                Err(e) => return Err(e),
+            }
         } else {
-            let mut to_submit = Err("couldn't find the right assignment, shutting down");
-            for idx in submission_candidate_indices {
-                match validate_assignment(&assignments[idx]) {
-                    Ok(Some(_)) => {
-                        to_submit = Ok(&assignments[idx]);
-                        break;
-                    }
-                    Ok(None) => continue,
-                    Err(_) => return Err("error reading from stdin")?,
-                }
+            match select_assignment(&assignments, &submission_candidate_indices) {
+                Ok(Some(assignment)) => Ok(assignment),
+                Ok(None) => Err("no assignment selected, shutting down"),
+                Err(e) => return Err(e),
             }
-            to_submit
         }
     }?;
 
+    // graded assignments only make it this far with --include-graded (or by
+    // being marked resubmittable), but resubmitting could still replace a
+    // grade, so double check unless the config says it's fine. --confirm-grade
+    // asks even then, and wants more than a y. --force skips both
+    let resubmittable = config.resubmittable(&to_submit.name);
+    if let Some(grade) = to_submit.grade.filter(|_| !matches.is_present("force")) {
+        if matches.is_present("confirm-grade") {
+            if !interactive {
+                return Err(format!(
+                    "{} has already been graded ({:.2}); pass --force to replace that grade non-interactively",
+                    to_submit.name, grade
+                ))?;
+            }
+            print!(
+                "{} is currently graded {:.2}, and a new submission may replace that with a worse grade. \
+                 type `replace` to submit anyways: ",
+                to_submit.name, grade
+            );
+            io::stdout().flush().unwrap();
+            if read_answer()?.trim() != "replace" {
+                return Err("keeping the current grade, not submitting")?;
+            }
+        } else if !resubmittable {
+            if !interactive {
+                return Err(format!(
+                    "{} has already been graded ({:.2}), not resubmitting without confirmation",
+                    to_submit.name, grade
+                ))?;
+            }
+            let prompt = format!(
+                "this assignment has already been graded ({:.2})! resubmit anyways? [y/N] ",
+                grade
+            );
+            if !confirm(&prompt)? {
+                return Err("not submitting assignment, shutting down")?;
+            }
+        }
+    }
+
     // we also must check if the assignment would be late, and warn the user if they're trying to submit a late assignment.
-    // it's impossible to try to submit to a graded assignment.
-    if to_submit.late() {
-        print!(
-            "{}",
-            format!(
-                "this assignment is {} hours late! submit anyways? [y/N] ",
-                to_submit.how_late()
-            )
+    // this one is asked even for resubmittable assignments, since deadlines still count
+    if let Some(how_late) = to_submit.how_late().filter(|_| to_submit.late()) {
+        if !interactive {
+            return Err(format!(
+                "{} is {} late, not submitting it without confirmation",
+                to_submit.name,
+                format_duration(how_late)
+            ))?;
+        }
+        let prompt = format!(
+            "this assignment is {} late! submit anyways? [y/N] ",
+            format_duration(how_late)
         );
-        io::stdout().flush().unwrap();
-
-        // determine if the user still wants to submit
-        loop {
-            let mut ans = String::new();
-            io::stdin().read_line(&mut ans)?;
-
-            match ans.trim().to_lowercase().as_str() {
-                "y" | "yes" => break,
-                "n" | "" | "no" => return Err("not submitting assignment, shutting down")?,
-                _ => {
-                    println!("couldn't get response, trying again...");
-                    continue;
-                }
-            }
+        if !confirm(&prompt)? {
+            return Err("not submitting assignment, shutting down")?;
         }
     }
     println!("{:?}", to_submit);
-    println!("{:?}", to_submit.submission_link(course_id));
+    println!("{:?}", to_submit.submission_link(client.instance(), course_id));
 
     // now, finally, we can construct the request and submit the assignment.
     let submission_page = client
-        .get(to_submit.submission_link(course_id))
-        .header("Referer", "https://handins.ccs.neu.edu")
-        .send()
-        .await?
-        .text()
+        .fetch_text(client.get(to_submit.submission_link(client.instance(), course_id)))
         .await?;
 
     // need to ensure, here, that we land on the correct page: search for distinct element?
     let tree = Document::from(submission_page.as_str());
+    check_submission_form(&tree, &to_submit.name)?;
 
-    let token = tree
-        .find(Attr("name", "csrf-token"))
-        .next()
-        .unwrap()
-        .attr("content")
-        .unwrap();
+    let token = require_element(&tree, Attr("name", "csrf-token"), "the submission page's csrf token")?;
+    let token = require_attr(token, "content", "the submission page's csrf token")?;
+
+    let user_id = require_element(&tree, Attr("name", "submission[user_id]"), "the submission form's user id")?;
+    let user_id = require_attr(user_id, "value", "the submission form's user id")?;
 
-    let user_id = tree
-        .find(Attr("name", "submission[user_id]"))
+    // the form says what kind of submission it takes; only file uploads are
+    // supported, and older pages without the field are assumed to be those
+    let submission_type = tree
+        .find(Attr("name", "submission[type]"))
         .next()
-        .unwrap()
-        .attr("value")
-        .unwrap();
+        .and_then(|field| field.attr("value"))
+        .unwrap_or("FilesSub")
+        .to_owned();
+    if submission_type != "FilesSub" {
+        return Err(format!(
+            "{} takes a {} submission rather than files, which submit doesn't support yet",
+            to_submit.name, submission_type
+        )
+        .into());
+    }
 
-    println!("{:?}", String::from_utf8(buffer.clone()));
+    // a text answer only goes where the form has somewhere to put it
+    if text.is_some() && tree.find(Attr("name", "submission[text]")).next().is_none() {
+        return Err(format!("{} doesn't take a text answer, only files", to_submit.name).into());
+    }
+
+    // the submit button's label is sent along with the form, and it isn't
+    // the same for every kind of submission
+    let commit_label = tree
+        .find(Attr("name", "commit"))
+        .next()
+        .and_then(|button| button.attr("value"))
+        .unwrap_or("Submit files")
+        .to_owned();
 
-    let file = Part::bytes(buffer)
-        .file_name(file_name.clone())
-        .mime_str("application/octet-stream")?;
+    let file = upload_part(buffer, &file_name)?;
 
-    let submission = Form::new()
+    let mut submission = Form::new()
         .text("utf8", "✓")
         .text("authenticity_token", token.to_owned())
-        .text("submission[type]", "FilesSub")
+        .text("submission[type]", submission_type)
         .text("submission[assignment_id]", to_submit.id.to_string())
         .text("submission[user_id]", user_id.to_owned())
         .text("submission[time_taken]", format!("{:.1}", hours))
         .text("submission[student_notes]", notes)
         .part("submission[upload_file]", file)
-        .text("commit", "Submit files");
+        .text("commit", commit_label);
+    if let Some(text) = text {
+        submission = submission.text("submission[text]", text);
+    }
 
     println!("{:?}", submission);
 
     // DANGER: DO NOT ATTEMPT UNTIL UNGRADED HW AVAILABLE
     // let results_page = client
-    //     .post(to_submit.submission_link(course_id))
-    //     .multipart(submission)
-    //     .header("Referer", to_submit.submission_link(course_id))
-    //     .send()
+    //     .send(client
+    //         .post_from(to_submit.submission_link(client.instance(), course_id), &to_submit.submission_link(client.instance(), course_id))
+    //         .multipart(submission))
     //     .await?;
+    // let results_page = client::check_status(results_page)?;
 
     // println!("{:?}", results_page.headers());
 
     Ok(())
 }
 
-async fn handins_login<C: CookieStore + 'static>(store: Option<Arc<C>>) -> Result<Client, Box<dyn Error>> {
+// zips up HEAD of the git repository we're running in, returning the name
+// to upload it as, the zip itself, and the commit hash it was built from
+async fn git_archive() -> Result<(String, Vec<u8>, String), Box<dyn Error>> {
+    let rev_parse = tokio::process::Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .await
+        .map_err(|e| format!("couldn't run git: {}", e))?;
+    if !rev_parse.status.success() {
+        return Err(format!(
+            "couldn't find the current git commit: {}",
+            String::from_utf8_lossy(&rev_parse.stderr).trim()
+        ))?;
+    }
+    let commit = String::from_utf8_lossy(&rev_parse.stdout).trim().to_owned();
+
+    let archive = tokio::process::Command::new("git")
+        .args(["archive", "--format=zip", "HEAD"])
+        .output()
+        .await
+        .map_err(|e| format!("couldn't run git: {}", e))?;
+    if !archive.status.success() {
+        return Err(format!(
+            "git archive failed: {}",
+            String::from_utf8_lossy(&archive.stderr).trim()
+        ))?;
+    }
+
+    let file_name = format!("{}.zip", &commit[..commit.len().min(12)]);
+    Ok((file_name, archive.stdout, commit))
+}
+
+// what the submission page says in place of the form once the deadline,
+// extensions included, has passed
+const CLOSED_BANNER: &str = "submissions are closed for this assignment";
+
+// the new-submission page only has an upload form while submissions are
+// open, so figure out why it's missing rather than failing to scrape it
+fn check_submission_form(tree: &Document, assignment: &str) -> Result<(), Box<dyn Error>> {
+    if tree.find(Attr("name", "submission[user_id]")).next().is_some() {
+        return Ok(());
+    }
+
+    // handins' own banner, not just any page that says "closed" somewhere
+    let closed = tree.find(Text).any(|text| {
+        let text = text.text().split_whitespace().collect::<Vec<_>>().join(" ");
+        text.to_lowercase().contains(CLOSED_BANNER)
+    });
+
+    if tree.find(Attr("name", "user[password]")).next().is_some() {
+        Err("your handins session expired before the submission page loaded, try again".into())
+    } else if closed {
+        Err(format!("submissions are closed for {}", assignment).into())
+    } else {
+        Err(format!("couldn't find the submission form for {}", assignment).into())
+    }
+}
+
+// the assignment name is the first non-blank line of the file, so build
+// scripts can keep it alongside the project
+async fn read_assignment_file(path: &str) -> Result<String, Box<dyn Error>> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("couldn't read assignment file {}: {}", path, e))?;
+
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+        .ok_or_else(|| format!("assignment file {} is empty", path).into())
+}
+
+async fn handins_login<C: CookieStore + 'static>(
+    store: Option<Arc<C>>,
+    instance: Box<dyn Instance>,
+    username: Option<&str>,
+    password_fd: Option<i32>,
+    interactive: bool,
+    options: &ClientOptions,
+) -> Result<Handins, Box<dyn Error>> {
     // initialize a new client and login to the user's homepage, so we can do more from there
     let client = {
+        let builder = Client::builder().user_agent(options.user_agent.as_str());
         if let Some(store) = store {
-            Client::builder()
+            builder
                 .cookie_provider(store)
                 .build()
                 .expect("couldn't create client to connect to internet")
         }
         else {
-            Client::builder()
+            builder
                 .cookie_store(true)
                 .build()
                 .expect("couldn't create client to connect to internet")
         }
     };
-     
-
-    let (username, password) = get_login_credentials()?;
+    let client = Handins::new(client, instance, options);
+    let login_url = client.instance().url(client.instance().login_path());
+    // instances can put their login page somewhere other than /login/
+    let login_path = reqwest::Url::parse(&login_url)?
+        .path()
+        .trim_end_matches('/')
+        .to_owned();
 
-    let login_page = client
-        .get("https://handins.ccs.neu.edu/login/")
-        .send()
-        .await?
-        .text()
+    let (landed_on, login_page) = client
+        .fetch_page(client.get(&login_url))
         .await?;
 
-    let tree = Document::from(login_page.as_str());
+    // if the login page bounced us somewhere else, handins has moved to
+    // single sign-on, and posting the usual form would silently leave us
+    // logged out. check before asking for credentials we couldn't use anyways
+    check_not_sso(client.instance(), &landed_on)?;
+
+    let (username, mut password) = get_login_credentials(username, password_fd, interactive)?;
+
+    // a wrong password, often a stale HANDINS_PASSWORD after changing it,
+    // gets one more try typed in by hand
+    let mut login_page = login_page;
+    let mut retried = false;
+    loop {
+        let tree = Document::from(login_page.as_str());
+        let form = login_form(&tree, &landed_on)?;
+
+        let params = [
+            ("utf8", "%E2%9C%93"),
+            (form.token_field, form.token),
+            (form.username_field, username.as_str()),
+            (form.password_field, password.as_str()),
+            form.commit,
+        ];
+
+        let logged_in = client
+            .send(client
+                .post_from(&login_url, &login_url)
+                .form(&params))
+            .await?;
+
+        check_not_sso(client.instance(), logged_in.url())?;
+
+        // a failed login sends us right back to the login page
+        if logged_in.url().path().starts_with(&login_path) {
+            let prompt = format!("couldn't log in to handins as {}. try another password? [y/N] ", username);
+            if retried || !interactive || !confirm(&prompt)? {
+                return Err("couldn't log in to handins, check your username and password")?;
+            }
+            retried = true;
+            password = prompt_password()?;
+
+            // the csrf token is only good for one attempt
+            login_page = client.fetch_text(client.get(&login_url)).await?;
+            continue;
+        }
+        client::check_status(logged_in)?;
+
+        // client is now logged in with the user session, so return it
+        return Ok(client);
+    }
+}
+
+// what the login form calls its fields. these are read off the page so a
+// rename on handins' end doesn't quietly break logging in, falling back to
+// the names handins has always used
+struct LoginForm<'a> {
+    token_field: &'a str,
+    token: &'a str,
+    username_field: &'a str,
+    password_field: &'a str,
+    commit: (&'a str, &'a str),
+}
+
+// reads the login form, after making sure this really is one
+fn login_form<'a>(tree: &'a Document, landed_on: &reqwest::Url) -> Result<LoginForm<'a>, Box<dyn Error>> {
+    let is_password = Name("input").and(Attr("type", "password"));
+    let form = match tree.find(Name("form")).find(|form| form.find(is_password).next().is_some()) {
+        Some(form) => form,
+        None => {
+            return Err(format!(
+                "{} doesn't look like the handins login form; if your account signs in \
+                 through single sign-on, this tool can't log in for you yet",
+                landed_on
+            )
+            .into())
+        }
+    };
+
+    let password_field = form
+        .find(is_password)
+        .next()
+        .and_then(|input| input.attr("name"))
+        .unwrap_or("user[password]");
+    let username_field = form
+        .find(Name("input"))
+        .filter(|input| matches!(input.attr("type"), None | Some("text") | Some("email")))
+        .find_map(|input| input.attr("name"))
+        .unwrap_or("user[username]");
+    let commit = form
+        .find(Name("input").and(Attr("type", "submit")))
+        .next()
+        .and_then(|button| Some((button.attr("name")?, button.attr("value")?)))
+        .unwrap_or(("commit", "Log+in"));
 
     // we need to grab the csrf-token from the metadata in the head, since
     // it's not properly set in the form initially
-    let token = tree
-        .find(Attr("name", "csrf-token"))
+    let token = require_element(tree, Attr("name", "csrf-token"), "the login page's csrf token")?;
+    let token = require_attr(token, "content", "the login page's csrf token")?;
+    let token_field = tree
+        .find(Attr("name", "csrf-param"))
         .next()
-        .unwrap()
-        .attr("content")
-        .unwrap();
-
-    let params = [
-        ("utf8", "%E2%9C%93"),
-        ("authenticity_token", token),
-        ("user[username]", username.as_str()),
-        ("user[password]", password.as_str()),
-        ("commit", "Log+in"),
-    ];
-
-    client
-        .post("https://handins.ccs.neu.edu/login/")
-        .form(&params)
-        .send()
-        .await?;
+        .and_then(|param| param.attr("content"))
+        .unwrap_or("authenticity_token");
+
+    Ok(LoginForm {
+        token_field,
+        token,
+        username_field,
+        password_field,
+        commit,
+    })
+}
 
-    // client is now logged in with the user session, so return it
-    Ok(client)
+fn check_not_sso(instance: &dyn Instance, url: &reqwest::Url) -> Result<(), Box<dyn Error>> {
+    match url.host_str() {
+        Some(host) if Some(host) == instance.host().as_deref() => Ok(()),
+        host => Err(format!(
+            "handins sent the login to {}, which looks like single sign-on; \
+             this tool only supports logging in with a handins username and password",
+            host.unwrap_or("an unknown site")
+        ))?,
+    }
 }
 
 async fn assignments(
-    client: &Client,
+    client: &Handins,
     course: i64,
 ) -> Result<Vec<Assignment>, Box<dyn Error>> {
-    let assignments = client
-        .get(format!(
-            "https://handins.ccs.neu.edu/courses/{}/assignments/",
-            course
-        ))
-        .header("Referer", "https://handins.ccs.neu.edu/")
-        .send()
-        .await?
-        .text()
+    let cached = cache::load_assignments(client, course);
+    let fetched = client
+        .fetch_if_changed(
+            client.get(client.instance().url(&format!("courses/{}/assignments/", course))),
+            cached.as_ref().map(|cached| &cached.validators),
+        )
         .await?;
 
-    let tree = Document::from(assignments.as_str());
+    // we only ask whether the page changed when there's a cached copy
+    let (page, validators) = match (fetched, cached) {
+        (Some(fetched), _) => fetched,
+        (None, Some(cached)) => return Ok(cached.assignments),
+        (None, None) => return Err("handins said the assignments page hadn't changed, but nothing was cached".into()),
+    };
 
-    let assignments: Vec<Assignment> = tree
-        .find(Name("tbody"))
-        .next()
-        .unwrap()
-        .find(Name("tr"))
-        .map(|row| {
-            let row_selection = row.find(Name("td")).into_selection();
-            let link = row_selection
-                .find(Attr("href", ()))
-                .first()
-                .unwrap()
-                .attr("href")
-                .unwrap()
-                .rsplit('/')
-                .next()
-                .unwrap()
-                .parse::<i64>()
-                .unwrap();
-            let name = row_selection.find(Text).first().unwrap().text();
-            let date = DateTime::parse_from_rfc3339(
-                &row_selection
-                    .find(Class("local-time"))
-                    .first()
-                    .unwrap()
-                    .text(),
-            )
-            .unwrap();
-            let weight = row
-                .find(Class("text-right"))
-                .next()
-                .unwrap()
-                .text()
-                .trim()
-                .to_owned();
-            let grade = row
-                .find(Class("text-right"))
-                .into_selection()
-                .next()
-                .next()
-                .first()
-                .unwrap()
-                .first_child()
-                .filter(|grade| grade.is(Text))
-                .map(|grade| grade.text());
-
-            let weight = weight.parse::<f64>().unwrap();
-            let grade = grade.map(|grade| grade.parse::<f64>().ok()).flatten();
-
-            Assignment::new(name, link, grade, weight, date)
-        })
-        .collect();
+    let assignments = client.time(&format!("parse the assignments for course {}", course), || {
+        parse_assignments(&page, client.strict())
+    })?;
+
+    if !validators.is_empty() {
+        let cached = CachedAssignments {
+            version: cache::CACHE_VERSION,
+            validators,
+            assignments,
+        };
+        cache::store_assignments(client, course, &cached);
+        return Ok(cached.assignments);
+    }
 
     Ok(assignments)
 }
 
-fn get_login_credentials() -> Result<(String, String), io::Error> {
-    print!("username: ");
-    io::stdout().flush().unwrap();
+// credentials come from $HANDINS_USERNAME / $HANDINS_PASSWORD (or
+// --password-fd) when they're set, and from prompts otherwise. when
+// prompting isn't allowed, missing credentials are an error instead
+fn get_login_credentials(
+    username: Option<&str>,
+    password_fd: Option<i32>,
+    interactive: bool,
+) -> Result<(String, String), Box<dyn Error>> {
+    // a --profile's username wins over the environment
+    let username = username
+        .map(|username| username.to_owned())
+        .or_else(|| std::env::var("HANDINS_USERNAME").ok());
+    let username = match username {
+        Some(username) if !username.is_empty() => username,
+        _ if !interactive => {
+            return Err("no username available: set HANDINS_USERNAME to log in non-interactively".into());
+        }
+        _ => {
+            print!("username: ");
+            io::stdout().flush().unwrap();
+
+            let mut username = String::new();
+            io::stdin()
+                .read_line(&mut username)
+                .map_err(|e| format!("couldn't read your username: {}", e))?;
+            if username.is_empty() {
+                println!();
+                return Err("no username provided!".into());
+            }
+            username
+        }
+    };
 
-    let mut username = String::new();
-    io::stdin().read_line(&mut username)?;
-    if username.is_empty() {
-        println!();
-        return Err(io::Error::new(
-            ErrorKind::InvalidInput,
-            "no username provided!",
-        ));
+    if let Some(fd) = password_fd {
+        let password = read_password_from_fd(fd)
+            .map_err(|e| format!("couldn't read your password from descriptor {}: {}", fd, e))?;
+        if password.is_empty() {
+            return Err("no password provided".into());
+        }
+        return Ok((username, password));
     }
 
-    let password = match rpassword::read_password_from_tty(Some("password: ")) {
+    match std::env::var("HANDINS_PASSWORD") {
+        Ok(password) if !password.is_empty() => return Ok((username, password)),
+        _ if !interactive => {
+            return Err("no password available: use --password-fd or set HANDINS_PASSWORD to log in non-interactively".into());
+        }
+        _ => {}
+    }
+
+    let password = prompt_password()?;
+
+    Ok((username, password))
+}
+
+fn prompt_password() -> Result<String, Box<dyn Error>> {
+    match rpassword::read_password_from_tty(Some("password: ")) {
         Ok(s) if s.is_empty() => {
             println!();
-            return Err(io::Error::new(
-                ErrorKind::InvalidInput,
-                "no password provided",
-            ));
+            Err("no password provided".into())
         }
-        Ok(s) => s,
+        Ok(s) => Ok(s),
         Err(e) => {
             println!();
-            return Err(e);
+            Err(format!("couldn't read your password: {}", e).into())
         }
-    };
+    }
+}
 
-    Ok((username, password))
+// reads the first line of an already-open file descriptor, like git's
+// credential helpers, so the password never shows up in argv or the environment
+#[cfg(unix)]
+fn read_password_from_fd(fd: i32) -> Result<String, io::Error> {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use std::mem::ManuallyDrop;
+    use std::os::unix::io::FromRawFd;
+
+    let mut password = String::new();
+    if fd == libc::STDIN_FILENO {
+        // stdin's own buffer may already hold the password, read in along
+        // with the username on the line before it
+        io::stdin().read_line(&mut password)?;
+    } else {
+        // safety: the caller handed us this descriptor explicitly, and we are
+        // the only ones reading from it. it stays open, since it was never
+        // ours to close
+        let file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+        BufReader::new(&*file).read_line(&mut password)?;
+    }
+
+    Ok(password.trim_end_matches(&['\r', '\n'][..]).to_owned())
 }
 
-fn validate_assignment(assignment: &Assignment) -> Result<Option<&Assignment>, Box<dyn Error>> {
-    loop {
+#[cfg(not(unix))]
+fn read_password_from_fd(_fd: i32) -> Result<String, io::Error> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "--password-fd is only supported on unix platforms",
+    ))
+}
+
+// one line of the user's answer to a prompt. running out of input (stdin
+// closed, or piped from something empty) would otherwise look like an empty
+// answer forever, so it's an error instead
+fn read_answer() -> Result<String, Box<dyn Error>> {
+    let mut ans = String::new();
+    let read = io::stdin()
+        .read_line(&mut ans)
+        .map_err(|e| format!("couldn't read an answer to the prompt: {}", e))?;
+    if read == 0 {
+        println!();
+        return Err("no input available to answer the prompt".into());
+    }
+    Ok(ans)
+}
+
+// how many unrecognized answers a prompt puts up with before giving up
+const MAX_PROMPT_ATTEMPTS: usize = 3;
+
+fn too_many_attempts() -> Box<dyn Error> {
+    format!("no recognizable answer after {} tries, giving up", MAX_PROMPT_ATTEMPTS).into()
+}
+
+fn validate_assignment<'a>(
+    assignments: &[Assignment],
+    assignment: &'a Assignment,
+) -> Result<Option<&'a Assignment>, Box<dyn Error>> {
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
         print!(
             "Did you want to submit to the following assignment: {}? [Y/n] ",
-            assignment.name
+            display_name(assignments, assignment)
         );
         io::stdout().flush().unwrap();
 
-        let mut ans = String::new();
-        io::stdin().read_line(&mut ans)?;
+        let ans = read_answer()?;
         let ans = ans.trim();
         if ans.is_empty() || ans.to_lowercase() == "y" {
             return Ok(Some(assignment));
@@ -581,61 +2403,360 @@ fn validate_assignment(assignment: &Assignment) -> Result<Option<&Assignment>, B
             println!("Couldn't get response, trying again...");
         }
     }
+    Err(too_many_attempts())
+}
+
+// asks a yes/no question, defaulting to no
+fn confirm(prompt: &str) -> Result<bool, Box<dyn Error>> {
+    print!("{}", prompt);
+    io::stdout().flush().unwrap();
+
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        let ans = read_answer()?;
+
+        match ans.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true),
+            "n" | "" | "no" => return Ok(false),
+            _ => {
+                println!("couldn't get response, trying again...");
+                continue;
+            }
+        }
+    }
+    Err(too_many_attempts())
 }
 
-// spring 2021 courses
-// will probably add a macro to convert a file w course names, number
-// to a lookup table, if numbers get updated each year
-fn lookup_course(course: &str) -> Result<i64, &str> {
-    match course.to_lowercase().as_str() {
-        "cs2500" | "fundies1" | "f1" => Ok(131),
-        "cs2510" | "fundies2" | "f2" => Ok(129),
-        "cs2510a" | "fundies2accel" | "f2accel" | "f2a" => Ok(126),
-        "cs3500" | "ood" => Ok(138),
-        "cs3500sp21" | "oodsp21" => Ok(133),
-        "cs4410" | "compilers" => Ok(127),
-        "cs4500" | "swdev" | "swe" => Ok(130),
-        _ => Err("Course not found"),
+// lets the user pick one of several fuzzy-matched candidates by number,
+// rather than walking through them one at a time
+fn select_assignment<'a>(
+    assignments: &'a [Assignment],
+    candidates: &[usize],
+) -> Result<Option<&'a Assignment>, Box<dyn Error>> {
+    println!("Several assignments matched:");
+    for (i, &idx) in candidates.iter().enumerate() {
+        println!("  {}) {}", i + 1, display_name(assignments, &assignments[idx]));
     }
+
+    for _ in 0..MAX_PROMPT_ATTEMPTS {
+        print!("Which one did you want to submit to? [1-{}, 0 to cancel] ", candidates.len());
+        io::stdout().flush().unwrap();
+
+        let ans = read_answer()?;
+
+        match ans.trim().parse::<usize>() {
+            Ok(0) => return Ok(None),
+            Ok(n) if n <= candidates.len() => return Ok(Some(&assignments[candidates[n - 1]])),
+            _ => println!("Couldn't get response, trying again..."),
+        }
+    }
+    Err(too_many_attempts())
 }
 
-fn calculate_grade(assignments: &[Assignment]) -> (f64, f64, f64, f64) {
-    let valid_weights: Vec<f64> = assignments
+// the assignment's name, with its due date when another assignment in the
+// course has the same name (a weekly "Lab", say), so they can be told apart
+fn display_name(assignments: &[Assignment], assignment: &Assignment) -> String {
+    let shared = assignments
         .iter()
-        .filter_map(|a| a.grade.map(|_| a.weight))
-        .collect();
+        .any(|other| other.id != assignment.id && other.name == assignment.name);
+    match assignment.due_date {
+        _ if !shared => assignment.name.clone(),
+        Some(_) => format!("{} (due {})", assignment.name, format::fmt_due_date(assignment)),
+        None => format!("{} (no due date)", assignment.name),
+    }
+}
+
+fn resolve_course<'a>(instance: &dyn Instance, course: &str) -> Result<i64, &'a str> {
+    instance.resolve_course(course).ok_or("Course not found")
+}
+
+// indices of the assignments whose names fuzzily match `query`, best first
+fn fuzzy_match(assignments: &[Assignment], query: &str) -> Vec<usize> {
+    let mut engine: SimSearch<usize> = SimSearch::new();
+    for (i, item) in assignments.iter().enumerate() {
+        engine.insert(i, &normalize_name(&item.name));
+    }
+    engine.search(&normalize_name(query))
+}
+
+// how closely `query` matches an assignment name, from 0 to 1. like the
+// search engine, each word of the query is scored against its closest word
+// in the name, and those scores are averaged
+fn match_score(name: &str, query: &str) -> f64 {
+    let name = normalize_name(name);
+    let query = normalize_name(query);
+    let words: Vec<&str> = query.split(' ').filter(|word| !word.is_empty()).collect();
+    if words.is_empty() {
+        return 0.0;
+    }
 
-    let future_weights: Vec<f64> = assignments
+    let total: f64 = words
         .iter()
-        .filter_map(|a| match a.grade {
-            Some(_) => None,
-            None => Some(a.weight),
+        .map(|word| {
+            name.split(' ')
+                .map(|part| strsim::jaro_winkler(part, word))
+                .fold(0.0, f64::max)
         })
+        .sum();
+    total / words.len() as f64
+}
+
+// parses `name=grade,other=grade` into hypothetical grades for ungraded
+// assignments, matching names the same way submit does
+fn parse_whatif(assignments: &[Assignment], whatif: &str) -> Result<Vec<(usize, f64)>, Box<dyn Error>> {
+    let ungraded: Vec<usize> = (0..assignments.len())
+        .filter(|&i| !assignments[i].graded())
         .collect();
+    let pool: Vec<Assignment> = ungraded.iter().map(|&i| assignments[i].clone()).collect();
+
+    let mut hypotheticals = vec![];
+    for pair in whatif.split(',').filter(|pair| !pair.trim().is_empty()) {
+        let (name, grade) = match pair.rsplit_once('=') {
+            Some((name, grade)) => (name.trim(), grade.trim()),
+            None => return Err(format!("`{}` should look like name=grade", pair))?,
+        };
+        let grade = grade
+            .parse::<f64>()
+            .map_err(|_| format!("`{}` isn't a grade", grade))?;
+
+        let idx = fuzzy_match(&pool, name)
+            .first()
+            .map(|&i| ungraded[i])
+            .ok_or_else(|| format!("`{}` didn't match any ungraded assignments", name))?;
+        hypotheticals.push((idx, grade));
+    }
 
-    let total_weight: f64 = valid_weights.iter().sum();
+    Ok(hypotheticals)
+}
 
-    let grades: Vec<f64> = assignments.iter().filter_map(|a| a.grade).collect();
+// the file being submitted, sent as the raw bytes it was read as. zips and
+// pdfs are never run through anything that expects text
+fn upload_part(buffer: Vec<u8>, file_name: &str) -> Result<Part, Box<dyn Error>> {
+    Ok(Part::bytes(buffer)
+        .file_name(file_name.to_owned())
+        .mime_str(mime_type(file_name))?)
+}
 
-    let scaled_grade = grades
-        .iter()
-        .zip(valid_weights.iter())
-        .fold(0.0, |sum, grade_pair| grade_pair.0 * grade_pair.1 + sum);
+// handins and the autograders look at the content type of an upload, so
+// send something better than octet-stream for the files students usually submit
+fn mime_type(file_name: &str) -> &'static str {
+    let extension = std::path::Path::new(file_name)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase());
+
+    match extension.as_deref() {
+        Some("rkt") => "text/x-racket",
+        Some("java") => "text/x-java",
+        Some("py") => "text/x-python",
+        Some("txt") => "text/plain",
+        Some("pdf") => "application/pdf",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
 
-    let future_weight: f64 = future_weights.iter().sum();
-    let optimistic_grade = scaled_grade + 100.0 * (100.0 - total_weight);
+// assignment names copied out of the web ui tend to pick up non-breaking
+// and zero-width spaces, or decomposed accents, so both the names we index
+// and the user's query go through this before fuzzy matching. any kind of
+// whitespace becomes one plain space, since the search engine splits names
+// into words on those
+fn normalize_name(s: &str) -> String {
+    let folded: String = s
+        .nfc()
+        .filter(|c| !is_zero_width(*c))
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .flat_map(char::to_lowercase)
+        .collect();
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
 
-    (
-        scaled_grade / total_weight, // your current grade
-        scaled_grade / 100.0,        // your minimum grade
-        optimistic_grade / 100.0,    // maximum possible grade for the course
-        // most points you can earn from ungraded assignments
-        // delta (max possible grade from ungraded assignments, current grade)
-        (scaled_grade + 100.0 * future_weight) / (total_weight + future_weight)
-            - (scaled_grade / total_weight),
-    )
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}' | '\u{feff}')
 }
 
-fn remove_whitespace(s: &str) -> String {
-    s.replace(char::is_whitespace, "")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assignment(name: &str, id: i64) -> Assignment {
+        Assignment::new(name.to_owned(), id, None, 10.0, None)
+    }
+
+    const LOGIN_PAGE: &str = include_str!("../fixtures/login.html");
+
+    fn login_url() -> reqwest::Url {
+        reqwest::Url::parse("https://handins.ccs.neu.edu/login/").unwrap()
+    }
+
+    #[test]
+    fn login_page_without_a_csrf_token_is_an_error() {
+        let page = LOGIN_PAGE.replace("<meta name=\"csrf-token\" content=\"selftest-token\">\n", "");
+        let tree = Document::from(page.as_str());
+        let err = login_form(&tree, &login_url()).err().unwrap();
+        assert!(err.to_string().contains("the login page's csrf token"), "{}", err);
+
+        let page = LOGIN_PAGE.replace(" content=\"selftest-token\"", "");
+        let tree = Document::from(page.as_str());
+        let err = login_form(&tree, &login_url()).err().unwrap();
+        assert!(err.to_string().contains("the content of the login page's csrf token"), "{}", err);
+    }
+
+    #[test]
+    fn login_page_without_a_password_field_is_not_a_login_form() {
+        let page = LOGIN_PAGE.replace("<input type=\"password\" name=\"user[password]\">\n", "");
+        let tree = Document::from(page.as_str());
+        let err = login_form(&tree, &login_url()).err().unwrap();
+        assert!(err.to_string().contains("doesn't look like the handins login form"), "{}", err);
+    }
+
+    // the start of a zip, plus bytes that can never appear in utf-8
+    const BINARY_FILE: &[u8] = b"PK\x03\x04\x14\x00\xff\xfe\x00\xc3\x28\x80 project.rkt";
+
+    // accepts one request, answers it with an empty 200, and hands back
+    // everything it was sent
+    async fn mock_server() -> (String, tokio::task::JoinHandle<Vec<u8>>) {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buf = [0; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if n == 0 || request_complete(&request) {
+                    break;
+                }
+            }
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            request
+        });
+
+        (url, server)
+    }
+
+    // whether the headers and as much body as they promised have arrived
+    fn request_complete(request: &[u8]) -> bool {
+        let end = match request.windows(4).position(|w| w == b"\r\n\r\n") {
+            Some(end) => end + 4,
+            None => return false,
+        };
+        let headers = String::from_utf8_lossy(&request[..end]).to_lowercase();
+        let length = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|length| length.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        request.len() >= end + length
+    }
+
+    #[tokio::test]
+    async fn binary_files_are_uploaded_untouched() {
+        let (url, server) = mock_server().await;
+
+        let form = Form::new()
+            .text("submission[type]", "FilesSub")
+            .part("submission[upload_file]", upload_part(BINARY_FILE.to_vec(), "project.zip").unwrap());
+        let response = Client::new().post(&url).multipart(form).send().await.unwrap();
+        assert!(response.status().is_success());
+
+        let request = server.await.unwrap();
+        assert!(request.windows(BINARY_FILE.len()).any(|w| w == BINARY_FILE));
+        let request = String::from_utf8_lossy(&request);
+        assert!(request.contains("filename=\"project.zip\""));
+        assert!(request.contains("Content-Type: application/zip"));
+    }
+
+    #[test]
+    fn closed_submissions_are_told_apart_from_a_missing_form() {
+        let closed = Document::from(
+            "<div class=\"alert alert-danger\">Submissions are closed\n  for this assignment.</div>",
+        );
+        let err = check_submission_form(&closed, "Lab 1").unwrap_err();
+        assert_eq!(err.to_string(), "submissions are closed for Lab 1");
+
+        // "closed" turning up somewhere else on the page doesn't count
+        let other = Document::from("<p>office hours are closed on Monday</p>");
+        let err = check_submission_form(&other, "Lab 1").unwrap_err();
+        assert_eq!(err.to_string(), "couldn't find the submission form for Lab 1");
+
+        let form = Document::from("<form><input name=\"submission[user_id]\" value=\"7\"></form>");
+        assert!(check_submission_form(&form, "Lab 1").is_ok());
+    }
+
+    #[test]
+    fn plan_rows_have_no_due_date() {
+        let plan = parse_plan_csv("name,grade,weight\nHomework 1,90,10\n\"Final, part 2\",,30\n").unwrap();
+        assert_eq!(plan.len(), 2);
+        assert_eq!(plan[1].name, "Final, part 2");
+        assert_eq!(plan[1].grade, None);
+        for assignment in &plan {
+            assert_eq!(assignment.due_date, None);
+            assert!(!assignment.late());
+            assert_eq!(assignment.how_late(), None);
+        }
+    }
+
+    #[test]
+    fn undated_assignments_sort_last() {
+        let date = |s| DateTime::parse_from_rfc3339(s).ok();
+        let mut assignments = vec![
+            assignment("Survey", 1),
+            Assignment::new("Lab 1".to_owned(), 2, None, 10.0, date("2021-01-22T21:00:00-05:00")),
+            Assignment::new("Lab 2".to_owned(), 3, None, 10.0, date("2021-01-29T21:00:00-05:00")),
+        ];
+        sort_newest_first(&mut assignments);
+
+        let ids: Vec<i64> = assignments.iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn course_names_are_normalized() {
+        let instance = instance::NeuCcs;
+        for course in ["cs2510", " cs2510 ", "CS2510", "CS 2510", "cs  2510", "Fundies2", "f2"] {
+            assert_eq!(resolve_course(&instance, course), Ok(129), "{:?}", course);
+        }
+        assert_eq!(resolve_course(&instance, "2510A"), Ok(126));
+        // a bare number is a handins course id, not a course number
+        assert_eq!(resolve_course(&instance, " 129 "), Ok(129));
+        assert_eq!(resolve_course(&instance, "2510"), Ok(2510));
+        assert!(resolve_course(&instance, "cs9999").is_err());
+        assert!(resolve_course(&instance, "").is_err());
+    }
+
+    #[test]
+    fn normalize_name_folds_unicode_whitespace() {
+        assert_eq!(normalize_name("Assignment\u{a0}3:\u{2003}Trees"), "assignment 3: trees");
+        assert_eq!(normalize_name("  Lab   1 "), "lab 1");
+        assert_eq!(normalize_name("Lab\u{200b}1"), "lab1");
+        // a decomposed é ends up the same as the composed one
+        assert_eq!(normalize_name("Caf\u{65}\u{301}"), normalize_name("Caf\u{e9}"));
+    }
+
+    #[test]
+    fn fuzzy_match_finds_single_words() {
+        let assignments = vec![
+            assignment("Assignment 1: Data Definitions", 1),
+            assignment("Assignment 3: Trees", 3),
+        ];
+        assert_eq!(fuzzy_match(&assignments, "trees").first(), Some(&1));
+        assert_eq!(fuzzy_match(&assignments, "Data Definitions").first(), Some(&0));
+    }
+
+    #[test]
+    fn fuzzy_match_sees_through_non_breaking_spaces() {
+        let assignments = vec![
+            assignment("Assignment\u{a0}1:\u{a0}Data\u{a0}Definitions", 1),
+            assignment("Assignment 3: Trees", 3),
+        ];
+        assert_eq!(fuzzy_match(&assignments, "definitions").first(), Some(&0));
+        assert_eq!(fuzzy_match(&assignments, "assignment\u{a0}3").first(), Some(&1));
+    }
 }
@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+use crate::assignment::Assignment;
+
+// bump this whenever one of the payloads below changes shape, so scripts
+// reading `--format json` can tell
+pub(crate) const SCHEMA_VERSION: u32 = 2;
+
+// every json payload goes out wrapped like this, saying which version of
+// which payload it is
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    schema_version: u32,
+    kind: &'a str,
+    #[serde(flatten)]
+    body: T,
+}
+
+pub(crate) fn print_json<T: Serialize>(kind: &str, body: T) {
+    let envelope = Envelope {
+        schema_version: SCHEMA_VERSION,
+        kind,
+        body,
+    };
+    // these are all plain structs of strings and numbers, so this can't fail
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&envelope).expect("couldn't serialize output")
+    );
+}
+
+// kind "grades"
+#[derive(Serialize)]
+pub(crate) struct Grades<'a> {
+    pub(crate) current_grade: f64,
+    pub(crate) minimum_grade: f64,
+    pub(crate) maximum_grade: f64,
+    pub(crate) points_left: f64,
+    pub(crate) assignments: &'a [Assignment],
+    // left out of the grades above by --drop-lowest
+    pub(crate) dropped: &'a [&'a Assignment],
+}
+
+// kind "sections"
+#[derive(Serialize)]
+pub(crate) struct Sections<'a> {
+    pub(crate) current_grade: f64,
+    pub(crate) minimum_grade: f64,
+    pub(crate) maximum_grade: f64,
+    pub(crate) points_left: f64,
+    pub(crate) graded: &'a [&'a Assignment],
+    pub(crate) ungraded: &'a [&'a Assignment],
+    pub(crate) dropped: &'a [&'a Assignment],
+}
+
+// kinds "ungraded" and "assignments"
+#[derive(Serialize)]
+pub(crate) struct Assignments<'a> {
+    pub(crate) assignments: &'a [&'a Assignment],
+}
+
+// kind "ungraded-count"
+#[derive(Serialize)]
+pub(crate) struct UngradedCount {
+    pub(crate) ungraded: usize,
+}
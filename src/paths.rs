@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+use directories::ProjectDirs;
+
+// where everything the tool keeps on disk lives. these follow XDG on linux
+// (~/.config/handins, ~/.cache/handins, ~/.local/share/handins), and the
+// platform conventions on macos and windows
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("edu", "neu", "handins")
+}
+
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.config_dir().to_owned())
+}
+
+pub(crate) fn cache_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.cache_dir().to_owned())
+}
+
+// nothing keeps data here yet, but history and the like should go here
+#[allow(dead_code)]
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    project_dirs().map(|dirs| dirs.data_dir().to_owned())
+}
+
+// $HANDINS_CONFIG if set, otherwise config.toml in the config directory
+pub(crate) fn config_file() -> Option<PathBuf> {
+    match std::env::var_os("HANDINS_CONFIG") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => config_dir().map(|dir| dir.join("config.toml")),
+    }
+}
@@ -0,0 +1,21 @@
+use std::path::Path;
+
+// a file in a project directory (or any directory above it) naming the
+// course that project is for, as an alias or a numeric id, so commands run
+// inside it can leave off COURSE
+pub(crate) const MARKER: &str = ".handins";
+
+pub(crate) fn marker_course() -> Option<String> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors().find_map(read_marker)
+}
+
+// the course is the first non-blank line, like an assignment file
+fn read_marker(dir: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(dir.join(MARKER)).ok()?;
+    contents
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .map(|line| line.to_owned())
+}
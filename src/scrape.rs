@@ -0,0 +1,239 @@
+use std::error::Error;
+use std::fmt;
+
+use chrono::DateTime;
+
+use select::document::Document;
+use select::node::Node;
+use select::predicate::{Attr, Class, Name, Predicate, Text};
+
+use crate::assignment::{Assignment, Points};
+
+// a page from handins didn't have something we rely on, usually because
+// the layout changed or we landed somewhere unexpected
+#[derive(Debug)]
+pub(crate) struct ParseError {
+    looking_for: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "couldn't find {} on the page handins sent back; its layout may have changed",
+            self.looking_for
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+// the first element matching `predicate`, where `context` describes it for
+// the error, e.g. "the login form's csrf token"
+pub(crate) fn require_element<'a, P: Predicate>(
+    doc: &'a Document,
+    predicate: P,
+    context: &str,
+) -> Result<Node<'a>, ParseError> {
+    doc.find(predicate).next().ok_or_else(|| ParseError {
+        looking_for: context.to_owned(),
+    })
+}
+
+pub(crate) fn require_attr<'a>(node: Node<'a>, attr: &str, context: &str) -> Result<&'a str, ParseError> {
+    node.attr(attr).ok_or_else(|| ParseError {
+        looking_for: format!("the {} of {}", attr, context),
+    })
+}
+
+// a row we can't make sense of is skipped, unless `strict` asks for the
+// whole thing to fail so the layout change can be tracked down
+pub(crate) fn parse_assignments(page: &str, strict: bool) -> Result<Vec<Assignment>, Box<dyn Error>> {
+    let tree = Document::from(page);
+
+    let rows = require_element(&tree, Name("tbody"), "the assignments table for this course")?
+        .find(Name("tr"));
+
+    let mut assignments = vec![];
+    for row in rows {
+        match parse_row(row, strict) {
+            Ok(assignment) => assignments.push(assignment),
+            Err(err) if strict => {
+                return Err(format!("{}, in this row:\n{}", err, row.html()).into());
+            }
+            Err(err) => eprintln!("warning: skipping a row of the assignments table: {}", err),
+        }
+    }
+
+    Ok(assignments)
+}
+
+fn parse_row(row: Node, strict: bool) -> Result<Assignment, String> {
+    // each of these walks the row once and stops at the first match, rather
+    // than collecting every cell into a selection up front
+    let href = row
+        .find(Attr("href", ()))
+        .next()
+        .and_then(|link| link.attr("href"))
+        .ok_or("no link to the assignment")?;
+    // `.../assignments/12/`, `.../assignments/12?tab=1` and the like still
+    // end in the id once the extras are trimmed off
+    let link = href
+        .split(['?', '#'])
+        .next()
+        .unwrap_or(href)
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .and_then(|id| id.parse::<i64>().ok())
+        .ok_or_else(|| format!("no assignment id at the end of {}", href))?;
+    let name = row
+        .find(Name("td").descendant(Text))
+        .next()
+        .ok_or("no assignment name")?
+        .text();
+    // no due date is fine, but one we can't read isn't
+    let date = row
+        .find(Class("local-time"))
+        .next()
+        .map(|date| {
+            let date = date.text();
+            DateTime::parse_from_rfc3339(date.trim())
+                .map_err(|_| format!("couldn't read the due date {:?}", date))
+        })
+        .transpose()?;
+
+    let weight_cell = row.find(Class("text-right")).next().ok_or("no weight")?;
+    let weight = weight_cell.text();
+    let weight = weight
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("couldn't read the weight {:?}", weight.trim()))?;
+    // the grade is the cell after the weight, past the whitespace between them
+    let grade_cell = weight_cell
+        .next()
+        .and_then(|gap| gap.next())
+        .ok_or("no grade column")?;
+    // a grade we can't read loses the grade, not the whole assignment
+    let parsed = parse_grade(grade_cell);
+    if parsed.is_none() && grade_cell.text().chars().any(|c| c.is_ascii_digit()) {
+        let err = format!("couldn't read the grade {:?}", grade_cell.text().trim());
+        if strict {
+            return Err(err);
+        }
+        eprintln!("warning: {}, counting {} as ungraded", err, name.trim());
+    }
+
+    let mut assignment = Assignment::new(name, link, parsed.map(|(grade, _)| grade), weight, date);
+    assignment.points = parsed.and_then(|(_, points)| points);
+    Ok(assignment)
+}
+
+// the grade is sometimes wrapped in a link or a span, so look through every
+// text node under the cell and take the first thing that reads as a number.
+// courses that grade out of points show "45 / 50" (or "45/50") instead of a
+// percentage, which comes back as the percentage along with the points
+fn parse_grade(cell: Node) -> Option<(f64, Option<Points>)> {
+    let text = cell
+        .find(Text)
+        .map(|text| text.text())
+        .collect::<Vec<_>>()
+        .join(" ");
+    // glue "45 / 50" back into one word
+    let text = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(" /", "/")
+        .replace("/ ", "/");
+
+    text.split_whitespace().find_map(|token| match token.split_once('/') {
+        Some((earned, possible)) => {
+            let points = Points {
+                earned: earned.parse().ok()?,
+                possible: possible.parse().ok()?,
+            };
+            if points.possible > 0.0 {
+                Some((points.percent(), Some(points)))
+            } else {
+                None
+            }
+        }
+        None => token.trim_end_matches('%').parse::<f64>().ok().map(|grade| (grade, None)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASSIGNMENTS_PAGE: &str = include_str!("../fixtures/assignments.html");
+    const LINKED_GRADES_PAGE: &str = include_str!("../fixtures/linked_grades.html");
+    const POINTS_GRADES_PAGE: &str = include_str!("../fixtures/points_grades.html");
+
+    fn grades(page: &str) -> Vec<(i64, Option<f64>)> {
+        parse_assignments(page, true)
+            .unwrap()
+            .iter()
+            .map(|a| (a.id, a.grade))
+            .collect()
+    }
+
+    #[test]
+    fn missing_elements_name_what_was_wanted() {
+        let doc = Document::from("<html><body><p>nothing here</p></body></html>");
+        let err = require_element(&doc, Name("tbody"), "the assignments table").unwrap_err();
+        assert!(err.to_string().contains("couldn't find the assignments table"), "{}", err);
+
+        let p = require_element(&doc, Name("p"), "a paragraph").unwrap();
+        let err = require_attr(p, "class", "a paragraph").unwrap_err();
+        assert!(err.to_string().contains("couldn't find the class of a paragraph"), "{}", err);
+    }
+
+    #[test]
+    fn a_page_without_the_table_is_an_error() {
+        let start = ASSIGNMENTS_PAGE.find("<table").unwrap();
+        let end = ASSIGNMENTS_PAGE.find("</table>").unwrap() + "</table>".len();
+        let page = format!("{}{}", &ASSIGNMENTS_PAGE[..start], &ASSIGNMENTS_PAGE[end..]);
+
+        let err = parse_assignments(&page, false).unwrap_err();
+        assert!(err.to_string().contains("the assignments table for this course"), "{}", err);
+    }
+
+    #[test]
+    fn grades_inside_links_and_spans_are_found() {
+        assert_eq!(
+            grades(LINKED_GRADES_PAGE),
+            vec![(2001, Some(88.5)), (2002, Some(92.0)), (2003, Some(71.0)), (2004, None)]
+        );
+    }
+
+    #[test]
+    fn point_grades_become_percentages() {
+        let parsed = parse_assignments(POINTS_GRADES_PAGE, true).unwrap();
+        let points: Vec<Option<(f64, f64)>> = parsed
+            .iter()
+            .map(|a| a.points.map(|points| (points.earned, points.possible)))
+            .collect();
+        assert_eq!(points, vec![Some((45.0, 50.0)), Some((18.0, 20.0)), Some((7.5, 10.0)), None]);
+        assert_eq!(
+            grades(POINTS_GRADES_PAGE),
+            vec![(3001, Some(90.0)), (3002, Some(90.0)), (3003, Some(75.0)), (3004, None)]
+        );
+    }
+
+    #[test]
+    fn unreadable_grades_count_as_ungraded() {
+        let page = POINTS_GRADES_PAGE.replace("<span>45 / 50</span>", "<span>- / 50</span>");
+
+        let lenient: Vec<(i64, Option<f64>)> = parse_assignments(&page, false)
+            .unwrap()
+            .iter()
+            .map(|a| (a.id, a.grade))
+            .collect();
+        assert_eq!(lenient, vec![(3001, None), (3002, Some(90.0)), (3003, Some(75.0)), (3004, None)]);
+
+        let err = parse_assignments(&page, true).unwrap_err();
+        assert!(err.to_string().contains("couldn't read the grade \"- / 50\""), "{}", err);
+    }
+}
@@ -0,0 +1,108 @@
+use std::error::Error;
+
+use chrono::DateTime;
+
+use reqwest::Url;
+
+use select::document::Document;
+
+use crate::assignment::{Assignment, Points};
+
+// pages laid out like handins', cut down to what the scrapers look at
+const ASSIGNMENTS_PAGE: &str = include_str!("../fixtures/assignments.html");
+const LOGIN_PAGE: &str = include_str!("../fixtures/login.html");
+
+// one self-test: Ok, or what came out wrong
+type Check = fn() -> Result<(), String>;
+
+// `selftest`: runs the scrapers over the bundled pages and checks what comes
+// out, no login needed. when one of these starts failing after handins
+// changes, the fixture needs updating first, and then the scraper
+pub(crate) fn run() -> Result<(), Box<dyn Error>> {
+    let checks: [(&str, Check); 2] = [
+        ("assignments table", check_assignments),
+        ("login form", check_login),
+    ];
+
+    let mut failed = 0;
+    for (name, check) in &checks {
+        match check() {
+            Ok(()) => println!("ok    {}", name),
+            Err(err) => {
+                println!("FAIL  {}: {}", name, err);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} self-tests failed", failed, checks.len()).into());
+    }
+    Ok(())
+}
+
+fn check_assignments() -> Result<(), String> {
+    let date = |s| DateTime::parse_from_rfc3339(s).ok();
+    let mut points = Assignment::new(
+        "Assignment 2: Lists".to_owned(),
+        1002,
+        Some(90.0),
+        5.0,
+        date("2021-01-29T21:00:00-05:00"),
+    );
+    points.points = Some(Points {
+        earned: 45.0,
+        possible: 50.0,
+    });
+
+    let expected = vec![
+        Assignment::new(
+            "Assignment 1: Data Definitions".to_owned(),
+            1001,
+            Some(95.0),
+            5.0,
+            date("2021-01-22T21:00:00-05:00"),
+        ),
+        points,
+        Assignment::new(
+            "Assignment 3: Trees".to_owned(),
+            1003,
+            None,
+            10.0,
+            date("2021-02-05T21:00:00-05:00"),
+        ),
+        Assignment::new("Midterm Survey".to_owned(), 1004, None, 0.0, None),
+    ];
+
+    let parsed = crate::scrape::parse_assignments(ASSIGNMENTS_PAGE, true).map_err(|e| e.to_string())?;
+    if parsed.len() != expected.len() {
+        return Err(format!("expected {} assignments, got {}", expected.len(), parsed.len()));
+    }
+    for (parsed, expected) in parsed.iter().zip(&expected) {
+        if parsed != expected {
+            return Err(format!("expected {:?}, got {:?}", expected, parsed));
+        }
+    }
+    Ok(())
+}
+
+fn check_login() -> Result<(), String> {
+    let url = Url::parse("https://handins.ccs.neu.edu/login/").expect("fixture url is valid");
+    let tree = Document::from(LOGIN_PAGE);
+
+    let form = crate::login_form(&tree, &url).map_err(|e| e.to_string())?;
+    let fields = [
+        ("csrf param", form.token_field, "authenticity_token"),
+        ("csrf token", form.token, "selftest-token"),
+        ("username field", form.username_field, "user[username]"),
+        ("password field", form.password_field, "user[password]"),
+        ("submit button name", form.commit.0, "commit"),
+        ("submit button value", form.commit.1, "Log in"),
+    ];
+    for (what, got, expected) in &fields {
+        if got != expected {
+            return Err(format!("expected the {} {}, got {}", what, expected, got));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,99 @@
+// rpassword turns off echo while reading the password, so if the user hits
+// ctrl-c at that prompt the shell is left not echoing anything. we remember
+// how the terminal was set up at startup so it can be put back on the way out
+#[cfg(unix)]
+pub(crate) struct TerminalState(Option<libc::termios>);
+
+#[cfg(unix)]
+impl TerminalState {
+    pub fn save() -> TerminalState {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+
+        // safety: tcgetattr only writes into the struct we hand it, and we
+        // only read it back if it reported success
+        let saved = unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) == 0 {
+                Some(termios.assume_init())
+            } else {
+                None
+            }
+        };
+
+        TerminalState(saved)
+    }
+
+    pub fn restore(&self) {
+        if let Some(termios) = &self.0 {
+            // safety: this is the same settings struct tcgetattr gave us
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios);
+            }
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) struct TerminalState;
+
+#[cfg(not(unix))]
+impl TerminalState {
+    pub fn save() -> TerminalState {
+        TerminalState
+    }
+
+    pub fn restore(&self) {}
+}
+
+// how many columns wide stdout is, if it's a terminal at all
+#[cfg(unix)]
+pub(crate) fn width() -> Option<usize> {
+    let mut size = std::mem::MaybeUninit::<libc::winsize>::uninit();
+
+    // safety: TIOCGWINSZ only fills in the struct we hand it, and we only
+    // read it back if it reported success
+    let size = unsafe {
+        if libc::isatty(libc::STDOUT_FILENO) == 1
+            && libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) == 0
+        {
+            size.assume_init()
+        } else {
+            return None;
+        }
+    };
+
+    Some(size.ws_col as usize).filter(|&cols| cols > 0)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn width() -> Option<usize> {
+    None
+}
+
+// exits cleanly on ctrl-c instead of dying mid-prompt
+pub(crate) fn exit_on_ctrl_c() {
+    let terminal = TerminalState::save();
+
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            terminal.restore();
+            eprintln!();
+            eprintln!("cancelled");
+            // let whatever --clipboard was holding reach the terminal first
+            let _ = crate::clipboard::finish();
+            std::process::exit(130);
+        }
+    });
+}
+
+// returned instead of calling `std::process::exit` deep inside a command, so
+// that main gets to finish up (the --clipboard capture, for one) first
+#[derive(Debug)]
+pub(crate) struct Exit(pub i32);
+
+impl std::fmt::Display for Exit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "exit code {}", self.0)
+    }
+}
+
+impl std::error::Error for Exit {}